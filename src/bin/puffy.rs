@@ -0,0 +1,83 @@
+//! A small sysctl(8) work-alike on top of the crate.
+//!
+//! ```text
+//! puffy -a                 print every known node
+//! puffy name ...           print nodes, or everything under a node
+//! puffy name=value ...     set nodes
+//! ```
+
+use puffy_sysctl::{get_value, set_from_str, walk, SysctlError, Value};
+
+use std::env;
+use std::process;
+
+fn usage() -> ! {
+    eprintln!("usage: puffy -a");
+    eprintln!("       puffy name[=value] ...");
+    process::exit(1);
+}
+
+fn print_all(values: Vec<(String, Value)>) {
+    for (name, value) in values {
+        println!("{}={}", name, value);
+    }
+}
+
+fn print(name: &str) -> puffy_sysctl::Result<()> {
+    match get_value(name) {
+        // a node has no value of its own, so show what's under it instead
+        Ok(Value::Node) | Err(SysctlError::IncompleteName(_)) => print_all(walk(name)?),
+        // some subtrees only resolve as the names under them
+        Err(e @ SysctlError::UnknownName(_)) => match walk(name) {
+            Ok(values) if !values.is_empty() => print_all(values),
+            _ => return Err(e),
+        },
+        Ok(value) => println!("{}={}", name, value),
+        Err(e) => return Err(e),
+    }
+
+    Ok(())
+}
+
+fn set(assignment: &str) -> puffy_sysctl::Result<()> {
+    set_from_str(assignment)?;
+
+    let name = assignment.split('=').next().unwrap_or(assignment);
+    print(name)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        usage();
+    }
+
+    if args[0] == "-a" {
+        if args.len() > 1 {
+            usage();
+        }
+        // walk leaves out whatever it can't read, so this prints all it can
+        match walk("") {
+            Ok(values) => print_all(values),
+            Err(e) => {
+                eprintln!("puffy: {}", e);
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    // like sysctl(8), keep going past a bad name and report it at the end
+    let mut failed = false;
+    for arg in &args {
+        let res = if arg.contains('=') { set(arg) } else { print(arg) };
+        if let Err(e) = res {
+            eprintln!("puffy: {}: {}", arg, e);
+            failed = true;
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
@@ -22,8 +22,13 @@ use libc::*;
 use nix::Error;
 
 use std::any::Any;
+use std::cell::Cell;
+use std::fmt;
 use std::mem;
 use std::ptr;
+use std::string::FromUtf8Error;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // bunch of consts that seem to be missing from libc
 const KERN_ALLOWKMEM: c_int = 54;
@@ -46,6 +51,10 @@ const KERN_SHMINFO_SHMMAX: c_int = 1;
 const KERN_SHMINFO_SHMMIN: c_int = 2;
 const KERN_SHMINFO_SHMMNI: c_int = 3;
 const KERN_SHMINFO_SHMSEG: c_int = 4;
+const KERN_TTY_TKNIN: c_int = 1;
+const KERN_TTY_TKNOUT: c_int = 2;
+const KERN_TTY_TKRAWCC: c_int = 3;
+const KERN_TTY_TKCANCC: c_int = 4;
 const KERN_TIMECOUNTER_CHOICE: c_int = 4;
 const KERN_TIMECOUNTER_HARDWARE: c_int = 3;
 const KERN_TIMECOUNTER_TICK: c_int = 1;
@@ -162,14 +171,109 @@ const AF_INET6: c_int = 24;
 const PF_INET: c_int = AF_INET;
 const PF_INET6: c_int = AF_INET6;
 
+const ICMP_MAXTYPE: usize = 40;
+
+// port bitmaps (struct baddynamicports) have a bit for every port
+const DP_MAPBITS: usize = 32;
+const DP_MAPSIZE: usize = 65536 / DP_MAPBITS;
+
 const CTL_DEBUG_NAME: c_int = 0;
 const CTL_DEBUG_VALUE: c_int = 1;
 const CTL_DEBUG_MAXID: c_int = 20;
 
-pub type Result<T> = std::result::Result<T, Error>;
+const KERN_POOL_NPOOLS: c_int = 1;
+const KERN_POOL_NAME: c_int = 2;
+const KERN_POOL_POOL: c_int = 3;
+
+pub type Result<T> = std::result::Result<T, SysctlError>;
+
+/// Everything that can go wrong making a `sysctl` call.
+#[derive(Debug, PartialEq)]
+pub enum SysctlError {
+    /// Some part of the name isn't one we know how to turn into a MIB.
+    UnknownName(String),
+    /// The kernel wouldn't let us read or change this node, usually because
+    /// only root may, or the securelevel is too high for the change.
+    PermissionDenied(String),
+    /// The name stops before reaching a leaf, e.g. `"kern.malloc"`.
+    IncompleteName(String),
+    /// Tried to write to a node the kernel won't let us change.
+    NotChangeable(String),
+    /// A value that can't go in the node, either given as text that can't
+    /// be turned into what the node holds, like `"fast"` for an int, or
+    /// outside the range the node accepts.
+    InvalidValue { name: String, value: String },
+    /// Gave up waiting for something to happen.
+    TimedOut,
+    /// The kernel handed back a different number of bytes than the type
+    /// we're decoding into takes up, or a MIB has more parts than
+    /// `CTL_MAXNAME` allows.
+    BadLength { expected: usize, got: usize },
+    /// The node holds a different kind of value than the call reads, e.g. a
+    /// string passed to `get_struct_bytes`.
+    WrongType { name: String, expected: SysctlType, got: SysctlType },
+    /// A numeric part of the name is past the end of what the kernel has,
+    /// e.g. a CPU that doesn't exist. Valid indices are below `max`.
+    IndexOutOfRange { name: String, index: usize, max: usize },
+    /// Tried to lower `kern.securelevel` while it's above 0, which only
+    /// init gets to do.
+    SecurelevelLowered { current: i32, requested: i32 },
+    /// A string node that isn't valid UTF-8.
+    Utf8(FromUtf8Error),
+    /// Whatever the OS (or nix) had to say.
+    Os(Error),
+}
+
+impl fmt::Display for SysctlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SysctlError::UnknownName(name) => write!(f, "unknown sysctl name: {}", name),
+            SysctlError::PermissionDenied(name) => {
+                write!(f, "permission denied for {}", name)
+            },
+            SysctlError::IncompleteName(name) => write!(f, "incomplete sysctl name: {}", name),
+            SysctlError::NotChangeable(name) => write!(f, "{} is not changeable", name),
+            SysctlError::InvalidValue { name, value } => {
+                write!(f, "{:?} is not a valid value for {}", value, name)
+            },
+            SysctlError::TimedOut => write!(f, "timed out"),
+            SysctlError::BadLength { expected, got } => {
+                write!(f, "expected {} bytes but got {}", expected, got)
+            },
+            SysctlError::WrongType { name, expected, got } => {
+                write!(f, "{} holds {:?}, not {:?}", name, got, expected)
+            },
+            SysctlError::IndexOutOfRange { name, index, max } => {
+                write!(f, "index {} in {} is out of range (max {})", index, name, max)
+            },
+            SysctlError::SecurelevelLowered { current, requested } => {
+                write!(f, "can't lower securelevel from {} to {}", current, requested)
+            },
+            SysctlError::Utf8(e) => write!(f, "{}", e),
+            SysctlError::Os(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SysctlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SysctlError::Utf8(e) => Some(e),
+            SysctlError::Os(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for SysctlError {
+    fn from(e: Error) -> SysctlError {
+        SysctlError::Os(e)
+    }
+}
 
+/// What kind of value a node holds.
 #[derive(Clone, Debug, PartialEq)]
-enum SysctlType {
+pub enum SysctlType {
     DevT,
     Int64,
     Int32,
@@ -181,10 +285,13 @@ enum SysctlType {
     UInt32Slice,
     UInt64Slice,
     UShortSlice,
+    Bytes,
 }
 
+/// A name resolved to its MIB, along with what it holds and whether it can
+/// be written to.
 #[derive(Clone, Debug, PartialEq)]
-struct Sysctl {
+pub struct Sysctl {
     mib: Vec<c_int>,
     value_type: SysctlType,
     changeable: bool,
@@ -197,9 +304,12 @@ struct Sysctl {
 macro_rules! sysctl_read {
     ($fn_name:ident, $sysctl_name:expr, $ty:ty) => {
         pub unsafe fn $fn_name(oldp: &mut $ty) -> $crate::Result<()> {
+            let mut oldlen = std::mem::size_of_val(&oldp[..]);
             $crate::sysctl_raw($sysctl_name,
                                oldp.as_mut_ptr() as *mut $crate::libc::c_void,
-                               std::ptr::null_mut())?;
+                               &mut oldlen,
+                               std::ptr::null_mut(),
+                               0)?;
             Ok(())
         }
     };
@@ -211,7 +321,9 @@ macro_rules! sysctl_write {
         pub unsafe fn $fn_name(oldp: &mut $ty, newp: &mut $ty) -> $crate::Result<()> {
             $crate::sysctl_raw($sysctl_name,
                                std::ptr::null_mut(),
-                               oldp.as_mut_ptr() as *mut $crate::libc::c_void)?;
+                               std::ptr::null_mut(),
+                               newp.as_mut_ptr() as *mut $crate::libc::c_void,
+                               std::mem::size_of_val(&newp[..]))?;
             Ok(())
         }
     };
@@ -221,56 +333,458 @@ macro_rules! sysctl_write {
 macro_rules! sysctl_readwrite {
     ($fn_name:ident, $sysctl_name:expr, $ty:ty) => {
         pub unsafe fn $fn_name(oldp: &mut $ty, newp: &mut $ty) -> $crate::Result<()> {
+            let mut oldlen = std::mem::size_of_val(&oldp[..]);
             $crate::sysctl_raw($sysctl_name,
                                oldp.as_mut_ptr() as *mut $crate::libc::c_void,
-                               newp.as_mut_ptr() as *mut $crate::libc::c_void)?;
+                               &mut oldlen,
+                               newp.as_mut_ptr() as *mut $crate::libc::c_void,
+                               std::mem::size_of_val(&newp[..]))?;
             Ok(())
         }
     };
 }
 
-pub fn sysctl_raw(name: &str, oldp: *mut c_void, newp: *mut c_void) -> Result<()> {
+/// Read and/or write `name` with a single `sysctl(2)` call, taking the same
+/// buffer arguments it does. Nothing is probed or resized; for that, use
+/// `read` or `get_struct_bytes`.
+///
+/// # Safety
+///
+/// `oldp` must be null or point to at least `*oldlenp` writable bytes, and
+/// `oldlenp` must be null or valid to read and write; on return it holds
+/// how many bytes the kernel wrote. `newp` must be null or point to `newlen`
+/// readable bytes.
+pub unsafe fn sysctl_raw(name: &str,
+                         oldp: *mut c_void,
+                         oldlenp: *mut usize,
+                         newp: *mut c_void,
+                         newlen: usize) -> Result<()> {
     // Management Information Base-style name
     let sysctl_s = parse_mib_str(name)?;
 
-    let mut len = mem::size_of::<*mut c_void>();
-    let mib_len = sysctl_s.mib.len();
-    let newp_len = CTL_MAXNAME as usize * mem::size_of::<*mut c_void>();
+    // catch writes to read-only nodes here, where we can still say which
+    // node it was, rather than getting a bare EPERM back from the kernel
+    if !newp.is_null() && !sysctl_s.changeable {
+        return Err(SysctlError::NotChangeable(name.to_string()));
+    }
+
+    let res = libc::sysctl(sysctl_s.mib.as_ptr(),
+                           sysctl_s.mib.len() as u32,
+                           oldp,
+                           oldlenp,
+                           newp,
+                           newlen);
+
+    if res < 0 {
+        return Err(last_os_error().into());
+    }
+
+    Ok(())
+}
+
+// errno as a nix error; only meaningful right after a call that failed
+fn last_os_error() -> Error {
+    Error::Sys(nix::errno::Errno::last())
+}
+
+/// Types that can be decoded from the raw bytes `sysctl` hands back (and
+/// encoded into bytes we can hand to it).
+pub trait SysctlValue: Sized {
+    fn from_sysctl(buf: &[u8]) -> Result<Self>;
+    fn to_sysctl(&self) -> Vec<u8>;
+}
+
+// reinterpret the buffer as a plain-old-data C struct, as long as the kernel
+// handed back exactly as many bytes as the struct takes up
+fn decode_struct<T: Copy>(buf: &[u8]) -> Result<T> {
+    if buf.len() != mem::size_of::<T>() {
+        return Err(SysctlError::BadLength { expected: mem::size_of::<T>(), got: buf.len() });
+    }
+
+    Ok(unsafe { ptr::read_unaligned(buf.as_ptr() as *const T) })
+}
+
+fn encode_struct<T: Copy>(val: &T) -> Vec<u8> {
+    let p = val as *const T as *const u8;
+
+    unsafe { std::slice::from_raw_parts(p, mem::size_of::<T>()) }.to_vec()
+}
+
+// structs that mirror a C struct byte-for-byte get their SysctlValue impl
+// for free
+macro_rules! sysctl_struct {
+    ($($ty:ty),*) => {
+        $(
+            impl SysctlValue for $ty {
+                fn from_sysctl(buf: &[u8]) -> Result<Self> {
+                    decode_struct(buf)
+                }
+
+                fn to_sysctl(&self) -> Vec<u8> {
+                    encode_struct(self)
+                }
+            }
+        )*
+    };
+}
+
+impl SysctlValue for String {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        // an unset string node can come back as zero bytes or as a lone NUL,
+        // and either way that's a perfectly good empty string
+        let end = buf.iter().position(|&b| b == b'\0').unwrap_or(buf.len());
+
+        String::from_utf8(buf[..end].to_vec()).map_err(SysctlError::Utf8)
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        let mut buf = self.as_bytes().to_vec();
+        buf.push(b'\0');
+        buf
+    }
+}
+
+impl SysctlValue for Vec<u8> {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        Ok(buf.to_vec())
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl SysctlValue for i64 {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        if buf.len() != mem::size_of::<i64>() {
+            return Err(SysctlError::BadLength { expected: mem::size_of::<i64>(), got: buf.len() });
+        }
+
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(buf);
+
+        Ok(i64::from_ne_bytes(bytes))
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        self.to_ne_bytes().to_vec()
+    }
+}
+
+impl SysctlValue for u64 {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        if buf.len() != mem::size_of::<u64>() {
+            return Err(SysctlError::BadLength { expected: mem::size_of::<u64>(), got: buf.len() });
+        }
+
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(buf);
+
+        Ok(u64::from_ne_bytes(bytes))
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        self.to_ne_bytes().to_vec()
+    }
+}
+
+impl SysctlValue for i32 {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        if buf.len() != mem::size_of::<i32>() {
+            return Err(SysctlError::BadLength { expected: mem::size_of::<i32>(), got: buf.len() });
+        }
+
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(buf);
+
+        Ok(i32::from_ne_bytes(bytes))
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        self.to_ne_bytes().to_vec()
+    }
+}
+
+impl SysctlValue for Vec<u32> {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        let size = mem::size_of::<u32>();
+        if buf.len() % size != 0 {
+            let expected = (buf.len() / size + 1) * size;
+            return Err(SysctlError::BadLength { expected, got: buf.len() });
+        }
+
+        Ok(buf.chunks(size)
+            .map(|chunk| {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(chunk);
+                u32::from_ne_bytes(bytes)
+            })
+            .collect())
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        self.iter().flat_map(|i| i.to_ne_bytes().to_vec()).collect()
+    }
+}
+
+impl SysctlValue for Vec<i32> {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        let size = mem::size_of::<i32>();
+        if buf.len() % size != 0 {
+            let expected = (buf.len() / size + 1) * size;
+            return Err(SysctlError::BadLength { expected, got: buf.len() });
+        }
+
+        buf.chunks(size).map(i32::from_sysctl).collect()
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        self.iter().flat_map(|i| i.to_sysctl()).collect()
+    }
+}
+
+// flags are just ints that are either 0 or 1
+impl SysctlValue for bool {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        Ok(i32::from_sysctl(buf)? != 0)
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        (*self as i32).to_sysctl()
+    }
+}
+
+/// Read a node and decode it as `T`, e.g. `read::<String>("kern.ostype")`.
+///
+/// A node that legitimately holds nothing (like an unset `hw.serialno`)
+/// decodes to an empty value; a node that can't be read at all is an error.
+pub fn read<T: SysctlValue>(name: &str) -> Result<T> {
+    let sysctl_s = parse_mib_str(name)?;
+    // an EPERM on a read means this node is root-only, which callers may
+    // want to handle differently than being refused a write
+    let buf = read_mib(&sysctl_s).map_err(|e| match e {
+        SysctlError::Os(Error::Sys(nix::errno::Errno::EPERM)) => {
+            SysctlError::PermissionDenied(name.to_string())
+        },
+        e => e,
+    })?;
+
+    T::from_sysctl(&buf)
+}
+
+/// Read a node that holds an array of ints.
+pub fn read_int_slice(name: &str) -> Result<Vec<i32>> {
+    read(name)
+}
+
+/// Read a struct node as raw bytes, sized by asking the kernel first, for
+/// structs the crate doesn't decode yet.
+pub fn get_struct_bytes(name: &str) -> Result<Vec<u8>> {
+    let sysctl_s = parse_mib_str(name)?;
+    if sysctl_s.value_type != SysctlType::SysStruct {
+        return Err(SysctlError::WrongType {
+            name: name.to_string(),
+            expected: SysctlType::SysStruct,
+            got: sysctl_s.value_type,
+        });
+    }
+
+    read_mib(&sysctl_s)
+}
+
+/// Encode `value` and write it to `name`.
+pub fn write<T: SysctlValue>(name: &str, value: &T) -> Result<()> {
+    let sysctl_s = parse_mib_str(name)?;
+
+    if !sysctl_s.changeable {
+        return Err(SysctlError::NotChangeable(name.to_string()));
+    }
+
+    write_mib(name, &sysctl_s, &value.to_sysctl())
+}
+
+/// Set a node from a `name=value` string the way `sysctl name=value` would,
+/// turning the value into whatever the node holds. Lists of ints are
+/// comma-separated. A value that doesn't fit the node, or no `=` at all, is
+/// an `InvalidValue`.
+pub fn set_from_str(assignment: &str) -> Result<()> {
+    let eq = assignment.find('=').ok_or_else(|| SysctlError::InvalidValue {
+        name: assignment.to_string(),
+        value: String::new(),
+    })?;
+    let (name, value) = (&assignment[..eq], &assignment[eq + 1..]);
+
+    let sysctl_s = parse_mib_str(name)?;
+    if !sysctl_s.changeable {
+        return Err(SysctlError::NotChangeable(name.to_string()));
+    }
+
+    let invalid = || SysctlError::InvalidValue {
+        name: name.to_string(),
+        value: value.to_string(),
+    };
+    let buf = match sysctl_s.value_type {
+        SysctlType::Int32 => value.parse::<i32>().map_err(|_| invalid())?.to_sysctl(),
+        SysctlType::Int64 | SysctlType::Long => {
+            value.parse::<i64>().map_err(|_| invalid())?.to_sysctl()
+        },
+        SysctlType::SysString => value.to_string().to_sysctl(),
+        SysctlType::UInt32Slice => {
+            value.split(',')
+                .map(|v| v.trim().parse::<u32>())
+                .collect::<std::result::Result<Vec<u32>, _>>()
+                .map_err(|_| invalid())?
+                .to_sysctl()
+        },
+        _ => return Err(invalid()),
+    };
+
+    write_mib(name, &sysctl_s, &buf)
+}
+
+// nodes that have gone by another name, as (old, current)
+const ALIASES: &[(&str, &str)] = &[
+    ("hw.physmem", "hw.physmem64"),
+    ("hw.usermem", "hw.usermem64"),
+];
+
+/// Like `read`, but if `name` has been renamed between releases, try its
+/// current name first and then the others it's been known by, so the same
+/// name works on old and new kernels alike.
+pub fn read_with_aliases<T: SysctlValue>(name: &str) -> Result<T> {
+    let current = ALIASES.iter()
+        .find(|&&(old, _)| old == name)
+        .map(|&(_, current)| current)
+        .unwrap_or(name);
+
+    let mut names = vec![current];
+    for &(old, new) in ALIASES {
+        if new == current && !names.contains(&old) {
+            names.push(old);
+        }
+    }
+
+    let mut res = Err(SysctlError::UnknownName(name.to_string()));
+    for name in names {
+        res = read(name);
+        match res {
+            Err(SysctlError::UnknownName(_))
+            | Err(SysctlError::Os(Error::Sys(nix::errno::Errno::ENOENT)))
+            | Err(SysctlError::Os(Error::Sys(nix::errno::Errno::EOPNOTSUPP))) => continue,
+            _ => break,
+        }
+    }
+
+    res
+}
+
+/// Poll `name` every `poll` until it reads differently than it did at first,
+/// and hand back the new value, or give up with `TimedOut` after `timeout`.
+pub fn wait_for_change(name: &str, poll: Duration, timeout: Duration) -> Result<Value> {
+    let start = Instant::now();
+    let initial = get_value(name)?;
+
+    while start.elapsed() < timeout {
+        thread::sleep(poll);
+
+        let current = get_value(name)?;
+        if current != initial {
+            return Ok(current);
+        }
+    }
+
+    Err(SysctlError::TimedOut)
+}
+
+// how many times to re-size and try again when a value grows out from under
+// us between finding out how big it is and reading it
+const ENOMEM_RETRIES: usize = 3;
+
+// ask the kernel how big the value is, then read exactly that many bytes
+fn read_mib(sysctl_s: &Sysctl) -> Result<Vec<u8>> {
+    let mut retries = 0;
+    loop {
+        let mut len = 0;
 
-    // if we're getting a string we have to get the length from sysctl before
-    // actually passing in the buffer we want the string written to and 
-    // allocate space for the buffer based on that
-    if sysctl_s.value_type == SysctlType::SysString {
         let res = unsafe {
             libc::sysctl(sysctl_s.mib.as_ptr(),
-                         mib_len as u32,
-                         ptr::null_mut() as *mut c_void,
+                         sysctl_s.mib.len() as u32,
+                         ptr::null_mut(),
                          &mut len,
-                         ptr::null_mut() as *mut c_void,
+                         ptr::null_mut(),
                          0)
         };
 
         if res < 0 {
-            let e = nix::errno::errno();
-            return Err(Error::Sys(nix::errno::from_i32(e)));
+            return Err(last_os_error().into());
+        }
+
+        match read_mib_len(sysctl_s, len) {
+            Err(SysctlError::Os(Error::Sys(nix::errno::Errno::ENOMEM)))
+                if retries < ENOMEM_RETRIES => retries += 1,
+            res => return res,
         }
     }
+}
+
+// read into a buffer of `len` bytes, for when we already know how big the
+// value is going to be
+fn read_mib_len(sysctl_s: &Sysctl, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+
+    // nothing to read, so don't bother making the call
+    if len == 0 {
+        return Ok(buf);
+    }
+
+    let len = get_raw_mib(&sysctl_s.mib, &mut buf)?;
+    buf.truncate(len);
+
+    Ok(buf)
+}
+
+/// Read a numeric OID straight into `buf`, for when the MIB came from
+/// somewhere other than a name, and return how many bytes the kernel wrote.
+pub fn get_raw_mib(mib: &[c_int], buf: &mut [u8]) -> Result<usize> {
+    if mib.len() > CTL_MAXNAME as usize {
+        return Err(SysctlError::BadLength { expected: CTL_MAXNAME as usize, got: mib.len() });
+    }
 
+    let mut len = buf.len();
     let res = unsafe {
-        libc::sysctl(sysctl_s.mib.as_ptr(),
-                     mib_len as u32,
-                     oldp,
+        libc::sysctl(mib.as_ptr(),
+                     mib.len() as u32,
+                     buf.as_mut_ptr() as *mut c_void,
                      &mut len,
-                     newp,
-                     newp_len)
+                     ptr::null_mut(),
+                     0)
+    };
+
+    if res < 0 {
+        return Err(last_os_error().into());
+    }
+
+    Ok(len)
+}
+
+fn write_mib(name: &str, sysctl_s: &Sysctl, new: &[u8]) -> Result<()> {
+    let res = unsafe {
+        libc::sysctl(sysctl_s.mib.as_ptr(),
+                     sysctl_s.mib.len() as u32,
+                     ptr::null_mut(),
+                     ptr::null_mut(),
+                     new.as_ptr() as *mut c_void,
+                     new.len())
     };
 
     if res < 0 {
-        let e = nix::errno::errno();
-        Err(Error::Sys(nix::errno::from_i32(e)))
-    } else {
-        Ok(())
+        return Err(match last_os_error() {
+            Error::Sys(nix::errno::Errno::EPERM) => SysctlError::PermissionDenied(name.to_string()),
+            e => e.into(),
+        });
     }
+
+    Ok(())
 }
 
 fn parse_mib_str(name: &str) -> Result<Sysctl> {
@@ -279,13 +793,18 @@ fn parse_mib_str(name: &str) -> Result<Sysctl> {
         .map(|s| format!("{}", s))
         .collect();
 
-    let res = get_sysctl(&args)?;
+    // the parsers only see what's left of the name by the time they run out
+    // of it, so put the whole thing in the error
+    let res = get_sysctl(&args).map_err(|e| match e {
+        SysctlError::IncompleteName(_) => SysctlError::IncompleteName(name.to_string()),
+        e => e,
+    })?;
 
     Ok(res)
 }
 
-fn get_sysctl(names: &Vec<String>) -> Result<Sysctl> {
-    match names[0].as_str() {
+fn get_sysctl(names: &[String]) -> Result<Sysctl> {
+    match segment(names, 0)? {
         "kern" => parse_mib_kern(&names[1..]),
         "vm" => parse_mib_vm(&names[1..]),
         "fs" => parse_mib_fs(&names[1..]),
@@ -295,7 +814,7 @@ fn get_sysctl(names: &Vec<String>) -> Result<Sysctl> {
         "machdep" => parse_mib_machdep(&names[1..]),
         "ddb" => parse_mib_ddb(&names[1..]),
         "vfs" => parse_mib_vfs(&names[1..]),
-        _ => Err(Error::invalid_argument()),
+        name => Err(SysctlError::UnknownName(name.to_string())),
     }
 }
 
@@ -305,7 +824,7 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
     let mut value_type = SysctlType::Int32;
     let mut changeable = false;
 
-    match names[0].as_str() {
+    match segment(names, 0)? {
         "ostype" => {
             mib.push(KERN_OSTYPE);
             value_type = SysctlType::SysString
@@ -396,7 +915,7 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
         "msgbufsize" => mib.push(KERN_MSGBUFSIZE),
         "malloc" => {
             mib.push(KERN_MALLOCSTATS);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "bucket" => {
                     mib.push(KERN_MALLOC_BUCKET);
                     value_type = SysctlType::Node;
@@ -412,59 +931,78 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
                 "kmemstat" => {
                     mib.push(KERN_MALLOC_KMEMSTAT);
                     value_type = SysctlType::Node;
+                    // kern.malloc.kmemstat.N is the stats for type N
+                    if let Some(index) = names.get(2) {
+                        mib.push(unnamed_leaf(index)?);
+                        value_type = SysctlType::SysStruct;
+                    }
                 },
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
-        "cp_time" => mib.push(KERN_CPTIME),
+        "cp_time" => {
+            mib.push(KERN_CPTIME);
+            value_type = SysctlType::Long;
+        },
         "nchstats" => {
             mib.push(KERN_NCHSTATS);
-            match names[1].as_str() {
-                "good_hits" => unimplemented!(),
-                "negative_hits" => unimplemented!(),
-                "bad_hits" => unimplemented!(),
-                "false_hits" => unimplemented!(),
-                "misses" => unimplemented!(),
-                "long_names" => unimplemented!(),
-                "pass2" => unimplemented!(),
-                "2passes" => unimplemented!(),
-                "ncs_revhits" => unimplemented!(),
-                "ncs_revmiss" => unimplemented!(),
-                "ncs_dothits" => unimplemented!(),
-                "nch_dotdothits" => unimplemented!(),
-                _ => return Err(Error::invalid_argument()),
+            value_type = SysctlType::SysStruct;
+            // the kernel hands back the whole struct in one go, so a field
+            // name doesn't change the MIB, it just has to be a real field
+            if let Some(field) = names.get(1) {
+                if !NCHSTATS_FIELDS.contains(&field.as_str()) {
+                    return Err(SysctlError::UnknownName(field.clone()));
+                }
             }
         },
         "forkstat" => {
             mib.push(KERN_FORKSTAT);
-            match names[1].as_str() {
-                "forks" => unimplemented!(),
-                "vforks" => unimplemented!(),
-                "tforks" => unimplemented!(),
-                "kthreads" => unimplemented!(),
-                "fork_pages" => unimplemented!(),
-                "vfork_pages" => unimplemented!(),
-                "tfork_pages" => unimplemented!(),
-                "kthread_pages" => unimplemented!(),
-                _ => return Err(Error::invalid_argument()),
+            value_type = SysctlType::SysStruct;
+            // same deal as nchstats, one struct with all the fields
+            if let Some(field) = names.get(1) {
+                if !FORKSTAT_FIELDS.contains(&field.as_str()) {
+                    return Err(SysctlError::UnknownName(field.clone()));
+                }
             }
         },
         "nselcoll" => mib.push(KERN_NSELCOLL),
         "tty" => {
             mib.push(KERN_TTY);
-            match names[1].as_str() {
-                "tk_nin" => unimplemented!(),
-                "tk_nout" => unimplemented!(),
-                "tk_rawcc" => unimplemented!(),
-                "tk_cancc" => unimplemented!(),
-                _ => return Err(Error::invalid_argument()),
+            value_type = SysctlType::Int64;
+            match names.get(1).map(|s| s.as_str()) {
+                Some("tk_nin") => mib.push(KERN_TTY_TKNIN),
+                Some("tk_nout") => mib.push(KERN_TTY_TKNOUT),
+                Some("tk_rawcc") => mib.push(KERN_TTY_TKRAWCC),
+                Some("tk_cancc") => mib.push(KERN_TTY_TKCANCC),
+                _ => return Err(unknown_segment(names, 1)),
             }
         },
         "ccpu" => mib.push(KERN_CCPU),
         "fscale" => mib.push(KERN_FSCALE),
         "nprocs" => mib.push(KERN_NPROCS),
         "msgbuf" => mib.push(KERN_MSGBUF),
-        "pool" => mib.push(KERN_POOL),
+        "pool" => {
+            mib.push(KERN_POOL);
+            value_type = SysctlType::Node;
+            // kern.pool.N is pool N's stats and kern.pool.name.N its name
+            match names.get(1).map(|s| s.as_str()) {
+                Some("npools") => {
+                    mib.push(KERN_POOL_NPOOLS);
+                    value_type = SysctlType::Int32;
+                },
+                Some("name") => {
+                    mib.push(KERN_POOL_NAME);
+                    mib.push(unnamed_leaf(segment(names, 2)?)?);
+                    value_type = SysctlType::SysString;
+                },
+                Some(index) => {
+                    mib.push(KERN_POOL_POOL);
+                    mib.push(unnamed_leaf(index)?);
+                    value_type = SysctlType::SysStruct;
+                },
+                None => (),
+            }
+        },
         "stackgap_random" => mib.push(KERN_STACKGAPRANDOM),
         "sysvipc_info" => mib.push(KERN_SYSVIPC_INFO),
         "allowkmem" => {
@@ -478,6 +1016,17 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
         "procargs" => {
             mib.push(KERN_PROC_ARGS);
             value_type = SysctlType::Node;
+            // kern.procargs.<pid>.argv or .env
+            if let Some(pid) = names.get(1) {
+                let pid = pid.parse::<pid_t>().map_err(|_| unknown_segment(names, 1))?;
+                mib.push(pid);
+                match names.get(2).map(|s| s.as_str()) {
+                    Some("argv") => mib.push(KERN_PROC_ARGV),
+                    Some("env") => mib.push(KERN_PROC_ENV),
+                    _ => return Err(unknown_segment(names, 2)),
+                }
+                value_type = SysctlType::Bytes;
+            }
         },
         "nfiles" => mib.push(KERN_NFILES),
         "ttycount" => mib.push(KERN_TTYCOUNT),
@@ -492,7 +1041,7 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
         },
         "seminfo" => {
             mib.push(KERN_SEMINFO);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "semmni" => {
                     mib.push(KERN_SEMINFO_SEMMNI);
                     changeable = true;
@@ -513,12 +1062,12 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
                 "semusz" => mib.push(KERN_SEMINFO_SEMUSZ),
                 "semvmx" => mib.push(KERN_SEMINFO_SEMVMX),
                 "semaem" => mib.push(KERN_SEMINFO_SEMAEM),
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "shminfo" => {
             mib.push(KERN_SHMINFO);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "shmmax" => {
                     mib.push(KERN_SHMINFO_SHMMAX);
                     changeable = true
@@ -539,7 +1088,7 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
                     mib.push(KERN_SHMINFO_SHMALL);
                     changeable = true;
                 },
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "intrcnt" => {
@@ -549,18 +1098,36 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
         "watchdog" => {
             mib.push(KERN_WATCHDOG);
             changeable = true;
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "period" => mib.push(KERN_WATCHDOG_PERIOD),
                 "auto" => mib.push(KERN_WATCHDOG_AUTO),
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
-        // TODO
         "proc" => {
             mib.push(KERN_PROC);
-            match names[1].as_str() {
-                "" => unimplemented!(),
-                _ => return Err(Error::invalid_argument()),
+            value_type = SysctlType::SysStruct;
+            // the kernel wants op, arg, the size of one kinfo_proc, and how
+            // many of them we have room for
+            match names.get(1).map(|s| s.as_str()) {
+                Some("pid") => {
+                    let pid = match names.get(2).map(|s| s.parse::<pid_t>()) {
+                        Some(Ok(pid)) => pid,
+                        _ => return Err(unknown_segment(names, 2)),
+                    };
+                    mib.push(KERN_PROC_PID);
+                    mib.push(pid);
+                    mib.push(mem::size_of::<KinfoProc>() as c_int);
+                    mib.push(1);
+                },
+                Some("all") => {
+                    mib.push(KERN_PROC_ALL);
+                    mib.push(0);
+                    mib.push(mem::size_of::<KinfoProc>() as c_int);
+                    // as many as fit in the buffer, which read_mib sizes
+                    mib.push(c_int::MAX);
+                },
+                _ => return Err(unknown_segment(names, 1)),
             }
         },
         "maxclusters" => {
@@ -571,7 +1138,7 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
         "evcount" => mib.push(KERN_EVCOUNT),
         "timecounter" => {
             mib.push(KERN_TIMECOUNTER);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "tick" => mib.push(KERN_TIMECOUNTER_TICK),
                 "timestepwarnings" => {
                     mib.push(KERN_TIMECOUNTER_TIMESTEPWARNINGS);
@@ -586,7 +1153,7 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
                     mib.push(KERN_TIMECOUNTER_CHOICE);
                     value_type = SysctlType::SysString;
                 },
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "maxlocksperuid" => {
@@ -596,6 +1163,11 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
         "cp_time2" => {
             mib.push(KERN_CPTIME2);
             value_type = SysctlType::UInt64Slice;
+            // the CPU we want the times for goes on the end
+            match names.get(1).map(|s| s.parse::<u32>()) {
+                Some(Ok(cpu)) => mib.push(cpu as c_int),
+                _ => return Err(unknown_segment(names, 1)),
+            }
         },
         "bufcachepercent" => {
             mib.push(KERN_CACHEPCT);
@@ -604,6 +1176,26 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
         "file" => {
             mib.push(KERN_FILE);
             value_type = SysctlType::SysStruct;
+            // like proc: op, arg, the size of one kinfo_file, and how many
+            // we have room for. kern.file alone is every open file
+            match names.get(1).map(|s| s.as_str()) {
+                None => {
+                    mib.push(KERN_FILE_BYFILE);
+                    // 0 for files of every type
+                    mib.push(0);
+                },
+                Some("pid") => {
+                    let pid = match names.get(2).map(|s| s.parse::<pid_t>()) {
+                        Some(Ok(pid)) => pid,
+                        _ => return Err(unknown_segment(names, 2)),
+                    };
+                    mib.push(KERN_FILE_BYPID);
+                    mib.push(pid);
+                },
+                _ => return Err(unknown_segment(names, 1)),
+            }
+            mib.push(mem::size_of::<KinfoFile>() as c_int);
+            mib.push(c_int::MAX);
         },
         "wxabort" => {
             mib.push(KERN_WXABORT);
@@ -619,22 +1211,29 @@ fn parse_mib_kern(names: &[String]) -> Result<Sysctl> {
         "pool_cwd" => mib.push(KERN_PROC_CWD),
         // TODO
         "proc_nobroadcastkill" => mib.push(KERN_PROC_NOBROADCASTKILL),
-        "proc_vmmap" => mib.push(KERN_PROC_VMMAP),
+        "proc_vmmap" => {
+            mib.push(KERN_PROC_VMMAP);
+            value_type = SysctlType::SysStruct;
+            // kern.proc_vmmap.<pid>
+            if let Some(pid) = names.get(1) {
+                mib.push(pid.parse::<pid_t>().map_err(|_| unknown_segment(names, 1))?);
+            }
+        },
         "global_ptrace" => mib.push(KERN_GLOBAL_PTRACE),
         // TODO
         "" => mib.push(KERN_CONSBUFSIZE),
         "" => mib.push(KERN_CONSBUF),
         "audio" => {
             mib.push(KERN_AUDIO);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "record" => {
                     mib.push(KERN_AUDIO_RECORD);
                     changeable = true;
                 },
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
-        _ => return Err(Error::invalid_argument()),
+        name => return Err(SysctlError::UnknownName(name.to_string())),
     };
 
     let res = Sysctl::new(mib, value_type, changeable)?;
@@ -647,7 +1246,7 @@ fn parse_mib_vm(names: &[String]) -> Result<Sysctl> {
     let mut value_type = SysctlType::Int32;
     let mut changeable = false;
 
-    match names[0].as_str() {
+    match segment(names, 0)? {
         "vmmeter" => {
             mib.push(VM_METER);
             value_type = SysctlType::SysStruct;
@@ -689,7 +1288,7 @@ fn parse_mib_vm(names: &[String]) -> Result<Sysctl> {
             value_type = SysctlType::SysString;
             changeable = true;
         },
-        _ => return Err(Error::invalid_argument()),
+        name => return Err(SysctlError::UnknownName(name.to_string())),
     };
 
     let res = Sysctl::new(mib, value_type, changeable)?;
@@ -700,15 +1299,15 @@ fn parse_mib_vm(names: &[String]) -> Result<Sysctl> {
 fn parse_mib_fs(names: &[String]) -> Result<Sysctl> {
     let mut mib = vec![CTL_FS as c_int];
 
-    match names[0].as_str() {
+    match segment(names, 0)? {
         "posix" => {
             mib.push(FS_POSIX);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "setuid" => mib.push(FS_POSIX_SETUID),
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
-        _ => return Err(Error::invalid_argument()),
+        name => return Err(SysctlError::UnknownName(name.to_string())),
     };
 
     let res = Sysctl::new(mib, SysctlType::Int32, true)?;
@@ -721,103 +1320,123 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
     let mut value_type = SysctlType::Int32;
     let mut changeable = false;
 
-    match names[0].as_str() {
+    match segment(names, 0)? {
         "route" => {
             mib.push(PF_ROUTE);
             // protocol number, always 0 for now
             mib.push(0);
-            mib.push(get_addr_family(&names[2])?);
-            match names[3].as_str() {
-                "dump" => {
-                    mib.push(NET_RT_DUMP);
-                    if names.len() > 4 {
-                        // TODO: get table from args
-                        let table = 0;
-                        mib.push(table);
-                    } else {
-                        mib.push(0);
-                    }
-                },
-                "flags" => {
-                    mib.push(NET_RT_FLAGS);
-                    // TODO: push rtflags onto mib
-                },
+            mib.push(get_addr_family(segment(names, 2)?)?);
+            match segment(names, 3)? {
+                "dump" => mib.push(NET_RT_DUMP),
+                "flags" => mib.push(NET_RT_FLAGS),
                 "iflist" => mib.push(NET_RT_IFLIST),
                 "ifnames" => mib.push(NET_RT_IFNAMES),
                 "stats" => mib.push(NET_RT_STATS),
                 "table" => mib.push(NET_RT_TABLE),
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
+            }
+            // every op takes an argument, 0 unless given: the priority for
+            // dump, the flags to match for flags, the interface index for
+            // iflist, the table for table
+            match names.get(4) {
+                Some(arg) => mib.push(unnamed_leaf(arg)?),
+                None => mib.push(0),
+            }
+            // then the routing table to look in, if not our own
+            if let Some(table) = names.get(5) {
+                mib.push(unnamed_leaf(table)?);
+            }
+            if let Some(extra) = names.get(6) {
+                return Err(SysctlError::UnknownName(extra.to_string()));
             }
+            // what comes back is a stream of routing messages
+            value_type = SysctlType::Bytes;
         },
         // TODO: parse the args that can get passed here
         "inet" => {
             mib.push(PF_INET);
             changeable = true;
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "ah" => {
                     mib.push(IPPROTO_AH);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "enable" => mib.push(1),
-                        "stats" => mib.push(2),
-                        _ => return Err(Error::invalid_argument()),
+                        "stats" => {
+                            mib.push(2);
+                            value_type = SysctlType::SysStruct;
+                            changeable = false;
+                        },
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "bpf" => {
                     mib.push(pseudo_AF_HDRCMPLT);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "bufsize" => mib.push(1),
                         "maxbufsize" => mib.push(2),
-                        _ => return Err(Error::invalid_argument()),
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "carp" => {
                     mib.push(IPPROTO_CARP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "allow" => mib.push(1),
                         "log" => mib.push(3),
                         "preempt" => mib.push(2),
-                        "stats" => mib.push(4),
-                        _ => return Err(Error::invalid_argument()),
+                        "stats" => {
+                            mib.push(4);
+                            value_type = SysctlType::SysStruct;
+                            changeable = false;
+                        },
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "divert" => {
                     mib.push(IPPROTO_DIVERT);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "recvspace" => mib.push(1),
                         "sendspace" => mib.push(2),
-                        "stats" => mib.push(3),
-                        _ => return Err(Error::invalid_argument()),
+                        "stats" => {
+                            mib.push(3);
+                            value_type = SysctlType::SysStruct;
+                            changeable = false;
+                        },
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "esp" => {
                     mib.push(IPPROTO_ESP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "enable" => mib.push(1),
                         "udpencap" => mib.push(2),
                         "udpencap_port" => mib.push(3),
-                        "stats" => mib.push(4),
-                        _ => return Err(Error::invalid_argument()),
+                        "stats" => {
+                            mib.push(4);
+                            value_type = SysctlType::SysStruct;
+                            changeable = false;
+                        },
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "etherip" => {
                     mib.push(IPPROTO_ETHERIP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "allow" => mib.push(1),
                         "stats" => mib.push(2),
-                        _ => return Err(Error::invalid_argument()),
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "gre" => {
                     mib.push(IPPROTO_GRE);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "allow" => mib.push(1),
                         "wccp" => mib.push(2),
-                        _ => return Err(Error::invalid_argument()),
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "icmp" => {
                     mib.push(IPPROTO_ICMP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "bmcastecho" => mib.push(2),
                         "errppslimit" => mib.push(3),
                         "maskrepl" => mib.push(1),
@@ -829,12 +1448,15 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                             changeable = false;
                         },
                         "tstamprepl" => mib.push(6),
-                        _ => return Err(Error::invalid_argument()),
+                        id => {
+                            mib.push(unnamed_leaf(id)?);
+                            value_type = SysctlType::Bytes;
+                        },
                     }
                 },
                 "ip" => {
                     mib.push(IPPROTO_IP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "arpdown" => mib.push(40),
                         "arptimeout" => mib.push(39),
                         "directed-broadcast" => mib.push(6),
@@ -843,12 +1465,12 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                         "ifq" => {
                             mib.push(30);
                             value_type = SysctlType::Node;
-                            match names[3].as_str() {
+                            match segment(names, 3)? {
                                 "congestion" => mib.push(4),
                                 "drops" => mib.push(3),
                                 "len" => mib.push(1),
                                 "maxlen" => mib.push(2),
-                                _ => return Err(Error::invalid_argument()),
+                                name => return Err(SysctlError::UnknownName(name.to_string())),
                             }
                         },
                         "ipsec-allocs" => mib.push(18),
@@ -880,7 +1502,7 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                         "mtudisctimeout" => mib.push(28),
                         "multipath" => mib.push(32),
                         "portfirst" => mib.push(7),
-                        "pirthifirst" => mib.push(9),
+                        "porthifirst" => mib.push(9),
                         "porthilast" => mib.push(10),
                         "portlast" => mib.push(8),
                         "redirect" => mib.push(2),
@@ -891,37 +1513,40 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                             changeable = false;
                         },
                         "ttl" => mib.push(3),
-                        _ => return Err(Error::invalid_argument()),
+                        id => {
+                            mib.push(unnamed_leaf(id)?);
+                            value_type = SysctlType::Bytes;
+                        },
                     }
                 },
                 "ipcomp" => {
                     mib.push(IPPROTO_IPCOMP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "enable" => mib.push(1),
                         "stats" => mib.push(2),
-                        _ => return Err(Error::invalid_argument()),
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "ipip" => {
                     mib.push(IPPROTO_IPIP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "allow" => mib.push(1),
                         "stats" => mib.push(2),
-                        _ => return Err(Error::invalid_argument()),
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "mobileip" => {
                     mib.push(IPPROTO_MOBILE);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "allow" => mib.push(1),
-                        _ => return Err(Error::invalid_argument()),
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "tcp" => {
                     mib.push(IPPROTO_TCP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "ackonpush" => mib.push(13),
-                        "always_keepalive" => mib.push(16),
+                        "always_keepalive" => mib.push(22),
                         "baddynamic" => {
                             mib.push(6);
                             value_type = SysctlType::UInt32Slice;
@@ -938,6 +1563,7 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                         "keepintvl" => mib.push(4),
                         "mssdflt" => mib.push(11),
                         "reasslimit" => mib.push(18),
+                        "recvspace" => mib.push(7),
                         "rfc1323" => mib.push(1),
                         "rfc3390" => mib.push(17),
                         "rootonly" => {
@@ -947,6 +1573,7 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                         "rstppslimit" => mib.push(12),
                         "sack" => mib.push(10),
                         "sackholelimit" => mib.push(20),
+                        "sendspace" => mib.push(8),
                         "slowhz" => {
                             mib.push(5);
                             changeable = false;
@@ -954,17 +1581,21 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                         "stats" => {
                             mib.push(21);
                             value_type = SysctlType::SysStruct;
+                            changeable = false;
                         },
                         "synbucketlimit" => mib.push(16),
                         "syncachelimit" => mib.push(15),
                         "synhashsize" => mib.push(25),
                         "synuselimit" => mib.push(23),
-                        _ => return Err(Error::invalid_argument()),
+                        id => {
+                            mib.push(unnamed_leaf(id)?);
+                            value_type = SysctlType::Bytes;
+                        },
                     }
                 },
                 "udp" => {
                     mib.push(IPPROTO_UDP);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "baddynamic" => {
                             mib.push(2);
                             value_type = SysctlType::UInt32Slice;
@@ -981,43 +1612,53 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                             value_type = SysctlType::SysStruct;
                             changeable = false;
                         },
-                        _ => return Err(Error::invalid_argument()),
+                        id => {
+                            mib.push(unnamed_leaf(id)?);
+                            value_type = SysctlType::Bytes;
+                        },
                     }
                 },
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "inet6" => {
             mib.push(PF_INET6);
             changeable = true;
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "divert" => {
                     mib.push(IPPROTO_DIVERT);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "recvspace" => mib.push(1),
                         "sendspace" => mib.push(2),
-                        "stats" => mib.push(3),
-                        _ => return Err(Error::invalid_argument()),
+                        "stats" => {
+                            mib.push(3);
+                            value_type = SysctlType::SysStruct;
+                            changeable = false;
+                        },
+                        name => return Err(SysctlError::UnknownName(name.to_string())),
                     }
                 },
                 "icmp6" => {
                     mib.push(IPPROTO_ICMPV6);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "errppslimit" => mib.push(14),
                         "mtudisc_hiwat" => mib.push(16),
                         "mtudisc_lowat" => mib.push(17),
                         "nd6_debug" => mib.push(18),
                         "nd6_delay" => mib.push(8),
                         "nd6_maxnudhint" => mib.push(15),
-                        "nd6_maxtries" => mib.push(10),
+                        "nd6_mmaxtries" => mib.push(10),
                         "nd6_umaxtries" => mib.push(9),
-                        "redirtimeout" => mib.push(17),
-                        _ => return Err(Error::invalid_argument()),
+                        "redirtimeout" => mib.push(3),
+                        id => {
+                            mib.push(unnamed_leaf(id)?);
+                            value_type = SysctlType::Bytes;
+                        },
                     }
                 },
                 "ip6" => {
                     mib.push(IPPROTO_IPV6);
-                    match names[2].as_str() {
+                    match segment(names, 2)? {
                         "auto_flowlabel" => mib.push(17),
                         "dad_count" => mib.push(16),
                         "dad_pending" => mib.push(49),
@@ -1045,27 +1686,30 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                             value_type = SysctlType::UInt8Slice;
                         },
                         "use_deprecated" => mib.push(21),
-                        _ => return Err(Error::invalid_argument()),
+                        id => {
+                            mib.push(unnamed_leaf(id)?);
+                            value_type = SysctlType::Bytes;
+                        },
                     }
                 },
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "key" => {
             mib.push(PF_KEY);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "sadb_dump" => mib.push(1),
                 "spd_dump" => mib.push(2),
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "mpls" => {
             mib.push(PF_MPLS);
             changeable = true;
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 //"ifq" => {
                 //    mib.push(3);
-                //    match names[2].as_str() {
+                //    match segment(names, 2)? {
                 //        "congestion" => mib.push(4),
                 //        "drops" => mib.push(3),
                 //        "len" => mib.push(1),
@@ -1077,54 +1721,29 @@ fn parse_mib_net(names: &[String]) -> Result<Sysctl> {
                 "mapttl_ip6" => mib.push(MPLSCTL_MAPTTL_IP6),
                 "maxloop_inkernel" => mib.push(MPLSCTL_MAXINKLOOP),
                 "ttl" => mib.push(MPLSCTL_DEFTTL),
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "pipex" => {
             mib.push(PF_PIPEX);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "enable" => {
                     mib.push(PIPEXCTL_ENABLE);
                     changeable = true;
                 },
-                "inq" => {
-                    mib.push(PIPEXCTL_INQ);
-                    match names[2].as_str() {
-                        "ifq" => {
-                            mib.push(30);
-                            value_type = SysctlType::Node;
-                            match names[3].as_str() {
-                                "congestion" => mib.push(4),
-                                "drops" => mib.push(3),
-                                "len" => mib.push(1),
-                                "maxlen" => mib.push(2),
-                                _ => return Err(Error::invalid_argument()),
-                            }
-                        },
-                        _ => return Err(Error::invalid_argument()),
-                    }
-                },
-                "outq" => {
-                    mib.push(PIPEXCTL_OUTQ);
-                    match names[3].as_str() {
-                        "ifq" => {
-                            mib.push(30);
-                            value_type = SysctlType::Node;
-                            match names[3].as_str() {
-                                "congestion" => mib.push(4),
-                                "drops" => mib.push(3),
-                                "len" => mib.push(1),
-                                "maxlen" => mib.push(2),
-                                _ => return Err(Error::invalid_argument()),
-                            }
-                        },
-                        _ => return Err(Error::invalid_argument()),
-                    }
+                // the queue counters hang straight off inq and outq, there's
+                // no ifq node in between like under net.inet.ip; we take
+                // net.pipex.inq.ifq.len as well as net.pipex.inq.len
+                "inq" | "outq" => {
+                    mib.push(if names[1] == "inq" { PIPEXCTL_INQ } else { PIPEXCTL_OUTQ });
+                    let i = if names.get(2).map(|s| s.as_str()) == Some("ifq") { 3 } else { 2 };
+                    mib.push(ifq_leaf(names, i)?);
+                    changeable = names[i] == "maxlen";
                 },
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
-        _ => return Err(Error::invalid_argument()),
+        name => return Err(SysctlError::UnknownName(name.to_string())),
     }
 
     let res = Sysctl::new(mib, value_type, changeable)?;
@@ -1137,10 +1756,29 @@ fn parse_mib_debug(names: &[String]) -> Result<Sysctl> {
     let mut value_type = SysctlType::Int32;
     let mut changeable = false;
 
-    match names[0].as_str() {
+    match segment(names, 0)? {
         "name" => mib.push(CTL_DEBUG_NAME),
         "value" => mib.push(CTL_DEBUG_VALUE),
-        _ => return Err(Error::invalid_argument()),
+        // each debug variable is its own node, debug.N, with its name and
+        // value under it
+        id => {
+            let index = unnamed_leaf(id)?;
+            if !(0..CTL_DEBUG_MAXID).contains(&index) {
+                return Err(SysctlError::UnknownName(id.to_string()));
+            }
+            mib.push(index);
+            match segment(names, 1)? {
+                "name" => {
+                    mib.push(CTL_DEBUG_NAME);
+                    value_type = SysctlType::SysString;
+                },
+                "value" => {
+                    mib.push(CTL_DEBUG_VALUE);
+                    changeable = true;
+                },
+                name => return Err(SysctlError::UnknownName(name.to_string())),
+            }
+        },
     }
 
     let res = Sysctl::new(mib, value_type, changeable)?;
@@ -1149,11 +1787,11 @@ fn parse_mib_debug(names: &[String]) -> Result<Sysctl> {
 }
 
 fn parse_mib_hw(names: &[String]) -> Result<Sysctl> {
-    let mut mib = Vec::new();
+    let mut mib = vec![CTL_HW as c_int];
     let mut value_type = SysctlType::Int32;
     let mut changeable = false;
 
-    match names[0].as_str() {
+    match segment(names, 0)? {
         "machine" => {
             mib.push(HW_MACHINE);
             value_type = SysctlType::SysString;
@@ -1180,6 +1818,19 @@ fn parse_mib_hw(names: &[String]) -> Result<Sysctl> {
         "sensors" => {
             mib.push(HW_SENSORS);
             value_type = SysctlType::Node;
+            // device number, sensor type, and index within that type
+            if names.len() > 4 {
+                return Err(unknown_segment(names, 4));
+            }
+            for i in 1..names.len() {
+                match names[i].parse::<c_int>() {
+                    Ok(id) => mib.push(id),
+                    Err(_) => return Err(unknown_segment(names, i)),
+                }
+            }
+            if names.len() == 4 {
+                value_type = SysctlType::SysStruct;
+            }
         },
         "cpuspeed" => mib.push(HW_CPUSPEED),
         "setperf" => {
@@ -1198,20 +1849,23 @@ fn parse_mib_hw(names: &[String]) -> Result<Sysctl> {
             mib.push(HW_VERSION);
             value_type = SysctlType::SysString;
         },
-        "serialno" => mib.push(HW_SERIALNO),
+        "serialno" => {
+            mib.push(HW_SERIALNO);
+            value_type = SysctlType::SysString;
+        },
         "uuid" => {
             mib.push(HW_UUID);
             value_type = SysctlType::SysString;
         },
-        "physmem" => {
+        "physmem" | "physmem64" => {
             mib.push(HW_PHYSMEM64);
             value_type = SysctlType::Int64;
         },
-        "usermem" => {
+        "usermem" | "usermem64" => {
             mib.push(HW_USERMEM64);
             value_type = SysctlType::Int64;
         },
-        "npcufound" => mib.push(HW_NCPUFOUND),
+        "ncpufound" => mib.push(HW_NCPUFOUND),
         "allowpowerdown" => {
             mib.push(HW_ALLOWPOWERDOWN);
             changeable = true
@@ -1226,7 +1880,7 @@ fn parse_mib_hw(names: &[String]) -> Result<Sysctl> {
             changeable = true;
         },
         "ncpuonline" => mib.push(HW_NCPUONLINE),
-        _ => return Err(Error::invalid_argument()),
+        name => return Err(SysctlError::UnknownName(name.to_string())),
     };
 
     let res = Sysctl::new(mib, value_type, changeable)?;
@@ -1240,12 +1894,12 @@ fn parse_mib_machdep(names: &[String]) -> Result<Sysctl> {
     // since these are machine-dependent, not every one will be available
     // on every piece of hardware, so I'm gonna just do amd64, which is the
     // only OpenBSD platform Rust builds on arfaict?
-    match names[0].as_str() {
+    match segment(names, 0)? {
         "allowaperture" => mib.push(MACHDEP_ALLOWAPERTURE),
         "kbdreset" => mib.push(MACHDEP_KBDRESET),
         "lidaction" => mib.push(MACHDEP_LIDACTION),
         "pwraction" => mib.push(MACHDEP_PWRACTION),
-        _ => return Err(Error::invalid_argument()),
+        name => return Err(SysctlError::UnknownName(name.to_string())),
     };
 
     let res = Sysctl::new(mib, SysctlType::Int32, true)?;
@@ -1256,7 +1910,7 @@ fn parse_mib_machdep(names: &[String]) -> Result<Sysctl> {
 fn parse_mib_ddb(names: &[String]) -> Result<Sysctl> {
     let mut mib = vec![CTL_DDB as c_int];
 
-    match names[0].as_str() {
+    match segment(names, 0)? {
         "radix" => mib.push(DBCTL_RADIX),
         "max_width" => mib.push(DBCTL_MAXWIDTH),
         "max_line" => mib.push(DBCTL_MAXLINE),
@@ -1266,7 +1920,7 @@ fn parse_mib_ddb(names: &[String]) -> Result<Sysctl> {
         "log" => mib.push(DBCTL_LOG),
         "trigger" => mib.push(DBCTL_TRIGGER),
         "profile" => mib.push(DBCTL_PROFILE),
-        _ => return Err(Error::invalid_argument()),
+        name => return Err(SysctlError::UnknownName(name.to_string())),
     };
 
     let res = Sysctl::new(mib, SysctlType::Int32, true)?;
@@ -1278,45 +1932,75 @@ fn parse_mib_vfs(names: &[String]) -> Result<Sysctl> {
     let mut mib = vec![CTL_VFS as c_int];
     let mut value_type = SysctlType::Int32;
     let mut changeable = false;
-match names[0].as_str() {
+match segment(names, 0)? {
         // not sure where these consts live, just using what the tree walking
         // in modified sysctl(8) spits out
         "mounts" => mib.push(0),
         "ffs" => {
             mib.push(1);
             changeable = true;
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "max_softdeps" => mib.push(FFS_MAX_SOFTDEPS),
                 "sd_tickdelay" => mib.push(FFS_SD_TICKDELAY),
-                "sd_worklist_push" => mib.push(FFS_SD_WORKLIST_PUSH),
-                "sd_blk_limit_push" => mib.push(FFS_SD_BLK_LIMIT_PUSH),
-                "sd_ino_limit_push" => mib.push(FFS_SD_INO_LIMIT_PUSH),
-                "sd_blk_limit_hit" => mib.push(FFS_SD_BLK_LIMIT_HIT),
-                "sd_ino_limit_hit" => mib.push(FFS_SD_INO_LIMIT_HIT),
-                "sd_sync_limit_hit" => mib.push(FFS_SD_SYNC_LIMIT_HIT),
-                "sd_indir_blk_ptrs" => mib.push(FFS_SD_INDIR_BLK_PTRS),
-                "sd_inode_bitmap" => mib.push(FFS_SD_INODE_BITMAP),
-                "sd_direct_blk_ptrs" => mib.push(FFS_SD_DIRECT_BLK_PTRS),
-                "sd_dir_entry" => mib.push(FFS_SD_DIR_ENTRY),
+                "sd_worklist_push" => {
+                    mib.push(FFS_SD_WORKLIST_PUSH);
+                    changeable = false;
+                },
+                "sd_blk_limit_push" => {
+                    mib.push(FFS_SD_BLK_LIMIT_PUSH);
+                    changeable = false;
+                },
+                "sd_ino_limit_push" => {
+                    mib.push(FFS_SD_INO_LIMIT_PUSH);
+                    changeable = false;
+                },
+                "sd_blk_limit_hit" => {
+                    mib.push(FFS_SD_BLK_LIMIT_HIT);
+                    changeable = false;
+                },
+                "sd_ino_limit_hit" => {
+                    mib.push(FFS_SD_INO_LIMIT_HIT);
+                    changeable = false;
+                },
+                "sd_sync_limit_hit" => {
+                    mib.push(FFS_SD_SYNC_LIMIT_HIT);
+                    changeable = false;
+                },
+                "sd_indir_blk_ptrs" => {
+                    mib.push(FFS_SD_INDIR_BLK_PTRS);
+                    changeable = false;
+                },
+                "sd_inode_bitmap" => {
+                    mib.push(FFS_SD_INODE_BITMAP);
+                    changeable = false;
+                },
+                "sd_direct_blk_ptrs" => {
+                    mib.push(FFS_SD_DIRECT_BLK_PTRS);
+                    changeable = false;
+                },
+                "sd_dir_entry" => {
+                    mib.push(FFS_SD_DIR_ENTRY);
+                    changeable = false;
+                },
                 "dirhash_dirsize" => mib.push(FFS_DIRHASH_DIRSIZE),
                 "dirhash_maxmem" => mib.push(FFS_DIRHASH_MAXMEM),
                 "dirhash_mem" => {
                     mib.push(FFS_DIRHASH_MEM);
                     changeable = false;
                 },
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "nfs" => {
             mib.push(3);
             changeable = true;
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "nfsstats" => {
                     mib.push(NFS_NFSSTATS);
                     value_type = SysctlType::SysStruct;
                 },
                 "iothreads" => mib.push(NFS_NIOTHREADS),
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
         "mfs" => mib.push(4),
@@ -1327,15 +2011,15 @@ match names[0].as_str() {
         "ext2fs" => mib.push(18),
         "fuse" => {
             mib.push(19);
-            match names[1].as_str() {
+            match segment(names, 1)? {
                 "fusefs_open_devices" => mib.push(FUSEFS_OPENDEVS),
                 "fusefs_fbufs_in" => mib.push(FUSEFS_INBUFS),
                 "fusefs_fbufs_wait" => mib.push(FUSEFS_WAITBUFS),
                 "fusefs_pool_pages" => mib.push(FUSEFS_POOL_NBPAGES),
-                _ => return Err(Error::invalid_argument()),
+                name => return Err(SysctlError::UnknownName(name.to_string())),
             }
         },
-        _ => return Err(Error::invalid_argument()),
+        name => return Err(SysctlError::UnknownName(name.to_string())),
     };
 
     let res = Sysctl::new(mib, value_type, changeable)?;
@@ -1343,45 +2027,166 @@ match names[0].as_str() {
     Ok(res)
 }
 
-fn get_addr_family(name: &str) -> Result<c_int> {
-    let af = match name {
-        "unix" => AF_UNIX,
-        "local" => AF_LOCAL,
-        "inet" => AF_INET,
-        "implink" => AF_IMPLINK,
-        "pup" => AF_PUP,
-        "chaos" => AF_CHAOS,
-        "ns" => AF_NS,
-        "iso" => AF_ISO,
-        "osi" => AF_OSI,
-        "ecma" => AF_ECMA,
-        "datakit" => AF_DATAKIT,
-        "ccitt" => AF_CCITT,
-        "sna" => AF_SNA,
-        "decnet" => AF_DECnet,
-        "dli" => AF_DLI,
-        "lat" => AF_LAT,
-        "hylink" => AF_HYLINK,
-        "appletalk" => AF_APPLETALK,
-        "route" => AF_ROUTE,
-        "link" => AF_LINK,
-        "coip" => AF_COIP,
-        "cnt" => AF_CNT,
-        "ipx" => AF_IPX,
-        "inet6" => AF_INET6,
-        "isdn" => AF_ISDN,
-        "e164" => AF_E164,
-        "natm" => AF_NATM,
-        "encap" => AF_ENCAP,
-        "sip" => AF_SIP,
-        "key" => AF_KEY,
-        "bluetooth" => AF_BLUETOOTH,
-        "mpls" => AF_MPLS,
-        "0" => 0,
-        _ => return Err(Error::invalid_argument()),
-    };
+// a bare number where we expected a name is taken as the id of a leaf the
+// crate doesn't know about (yet), so there's at least some way to reach it
+fn unnamed_leaf(name: &str) -> Result<c_int> {
+    name.parse::<c_int>().map_err(|_| SysctlError::UnknownName(name.to_string()))
+}
+
+// names[i], unless the name stops short of it
+fn segment(names: &[String], i: usize) -> Result<&str> {
+    names.get(i)
+        .map(|s| s.as_str())
+        .ok_or_else(|| SysctlError::IncompleteName(names.join(".")))
+}
+
+// the IFQCTL_* counters of an interface queue
+fn ifq_leaf(names: &[String], i: usize) -> Result<c_int> {
+    match names.get(i).map(|s| s.as_str()) {
+        Some("len") => Ok(1),
+        Some("maxlen") => Ok(2),
+        Some("drops") => Ok(3),
+        Some("congestion") => Ok(4),
+        _ => Err(unknown_segment(names, i)),
+    }
+}
+
+// the segment at `names[i]` we couldn't make sense of, or an incomplete name
+// if it stops short
+fn unknown_segment(names: &[String], i: usize) -> SysctlError {
+    match names.get(i) {
+        Some(name) => SysctlError::UnknownName(name.clone()),
+        None => SysctlError::IncompleteName(names.join(".")),
+    }
+}
+
+/// An address family, as named in `net.route` names (`"inet"`, `"inet6"`,
+/// and so on).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddrFamily {
+    /// Every family, written `"0"`.
+    Unspec,
+    Unix,
+    Local,
+    Inet,
+    ImpLink,
+    Pup,
+    Chaos,
+    Ns,
+    Iso,
+    Osi,
+    Ecma,
+    DataKit,
+    Ccitt,
+    Sna,
+    DecNet,
+    Dli,
+    Lat,
+    HyLink,
+    AppleTalk,
+    Route,
+    Link,
+    Coip,
+    Cnt,
+    Ipx,
+    Inet6,
+    Isdn,
+    E164,
+    Natm,
+    Encap,
+    Sip,
+    Key,
+    Bluetooth,
+    Mpls,
+}
+
+impl From<AddrFamily> for c_int {
+    fn from(af: AddrFamily) -> c_int {
+        match af {
+            AddrFamily::Unspec => AF_UNSPEC,
+            AddrFamily::Unix => AF_UNIX,
+            AddrFamily::Local => AF_LOCAL,
+            AddrFamily::Inet => AF_INET,
+            AddrFamily::ImpLink => AF_IMPLINK,
+            AddrFamily::Pup => AF_PUP,
+            AddrFamily::Chaos => AF_CHAOS,
+            AddrFamily::Ns => AF_NS,
+            AddrFamily::Iso => AF_ISO,
+            AddrFamily::Osi => AF_OSI,
+            AddrFamily::Ecma => AF_ECMA,
+            AddrFamily::DataKit => AF_DATAKIT,
+            AddrFamily::Ccitt => AF_CCITT,
+            AddrFamily::Sna => AF_SNA,
+            AddrFamily::DecNet => AF_DECnet,
+            AddrFamily::Dli => AF_DLI,
+            AddrFamily::Lat => AF_LAT,
+            AddrFamily::HyLink => AF_HYLINK,
+            AddrFamily::AppleTalk => AF_APPLETALK,
+            AddrFamily::Route => AF_ROUTE,
+            AddrFamily::Link => AF_LINK,
+            AddrFamily::Coip => AF_COIP,
+            AddrFamily::Cnt => AF_CNT,
+            AddrFamily::Ipx => AF_IPX,
+            AddrFamily::Inet6 => AF_INET6,
+            AddrFamily::Isdn => AF_ISDN,
+            AddrFamily::E164 => AF_E164,
+            AddrFamily::Natm => AF_NATM,
+            AddrFamily::Encap => AF_ENCAP,
+            AddrFamily::Sip => AF_SIP,
+            AddrFamily::Key => AF_KEY,
+            AddrFamily::Bluetooth => AF_BLUETOOTH,
+            AddrFamily::Mpls => AF_MPLS,
+        }
+    }
+}
+
+impl std::str::FromStr for AddrFamily {
+    type Err = SysctlError;
 
-    Ok(af)
+    fn from_str(name: &str) -> Result<AddrFamily> {
+        let af = match name {
+            "unix" => AddrFamily::Unix,
+            "local" => AddrFamily::Local,
+            "inet" => AddrFamily::Inet,
+            "implink" => AddrFamily::ImpLink,
+            "pup" => AddrFamily::Pup,
+            "chaos" => AddrFamily::Chaos,
+            "ns" => AddrFamily::Ns,
+            "iso" => AddrFamily::Iso,
+            "osi" => AddrFamily::Osi,
+            "ecma" => AddrFamily::Ecma,
+            "datakit" => AddrFamily::DataKit,
+            "ccitt" => AddrFamily::Ccitt,
+            "sna" => AddrFamily::Sna,
+            "decnet" => AddrFamily::DecNet,
+            "dli" => AddrFamily::Dli,
+            "lat" => AddrFamily::Lat,
+            "hylink" => AddrFamily::HyLink,
+            "appletalk" => AddrFamily::AppleTalk,
+            "route" => AddrFamily::Route,
+            "link" => AddrFamily::Link,
+            "coip" => AddrFamily::Coip,
+            "cnt" => AddrFamily::Cnt,
+            "ipx" => AddrFamily::Ipx,
+            "inet6" => AddrFamily::Inet6,
+            "isdn" => AddrFamily::Isdn,
+            "e164" => AddrFamily::E164,
+            "natm" => AddrFamily::Natm,
+            "encap" => AddrFamily::Encap,
+            "sip" => AddrFamily::Sip,
+            "key" => AddrFamily::Key,
+            "bluetooth" => AddrFamily::Bluetooth,
+            "mpls" => AddrFamily::Mpls,
+            "0" => AddrFamily::Unspec,
+            name => return Err(SysctlError::UnknownName(name.to_string())),
+        };
+
+        Ok(af)
+    }
+}
+
+fn get_addr_family(name: &str) -> Result<c_int> {
+    Ok(name.parse::<AddrFamily>()?.into())
 }
 
 impl Sysctl {
@@ -1392,18 +2197,4594 @@ impl Sysctl {
             changeable: changeable,
         })
     }
+
+    /// The numeric OID the name resolved to.
+    pub fn mib(&self) -> &[c_int] {
+        &self.mib
+    }
+
+    pub fn value_type(&self) -> &SysctlType {
+        &self.value_type
+    }
+
+    pub fn changeable(&self) -> bool {
+        self.changeable
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn call_sysctl() {
-        sysctl_read!(get_kern_ostype, "kern.ostype", Vec<u8>);
+/// A name resolved once, for reading the same node over and over without
+/// parsing the name every time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Handle {
+    name: String,
+    sysctl_s: Sysctl,
+    // how big a string node was last time, so reads after the first can
+    // skip asking; 0 until we know
+    last_len: Cell<usize>,
+}
 
-        let mut buf = vec![0u8; 256];
-        unsafe { get_kern_ostype(&mut buf).unwrap() };
-        buf.dedup_by(|a, b| *a == *b && *b == b'\0');
+impl Handle {
+    pub fn new(name: &str) -> Result<Handle> {
+        Ok(Handle {
+            name: name.to_string(),
+            sysctl_s: parse_mib_str(name)?,
+            last_len: Cell::new(0),
+        })
+    }
 
-        assert_eq!(String::from_utf8(buf).unwrap().as_str(), "OpenBSD\0");
+    /// Like `read`, without resolving the name again.
+    pub fn get<T: SysctlValue>(&self) -> Result<T> {
+        T::from_sysctl(&self.read_bytes()?)
+    }
+
+    /// Read the node's raw bytes.
+    ///
+    /// For string nodes the size from the last read is tried first, so it
+    /// only takes one syscall unless the string has grown since.
+    pub fn read_bytes(&self) -> Result<Vec<u8>> {
+        self.read_sized().map_err(|e| match e {
+            SysctlError::Os(Error::Sys(nix::errno::Errno::EPERM)) => {
+                SysctlError::PermissionDenied(self.name.clone())
+            },
+            e => e,
+        })
+    }
+
+    fn read_sized(&self) -> Result<Vec<u8>> {
+        if self.sysctl_s.value_type != SysctlType::SysString {
+            return read_mib(&self.sysctl_s);
+        }
+
+        // leave a byte spare: some string nodes truncate to fit rather than
+        // failing, so a buffer that comes back full may be missing the end
+        let len = self.last_len.get();
+        if len > 0 {
+            match read_mib_len(&self.sysctl_s, len + 1) {
+                Ok(buf) if buf.len() <= len => {
+                    self.last_len.set(buf.len());
+                    return Ok(buf);
+                },
+                // it's grown, so forget what we knew and ask again
+                Ok(_) | Err(SysctlError::Os(Error::Sys(nix::errno::Errno::ENOMEM))) => {
+                    self.last_len.set(0);
+                },
+                Err(e) => return Err(e),
+            }
+        }
+
+        let buf = read_mib(&self.sysctl_s)?;
+        self.last_len.set(buf.len());
+
+        Ok(buf)
+    }
+
+    /// The numeric OID the name resolved to.
+    pub fn mib(&self) -> &[c_int] {
+        &self.sysctl_s.mib
+    }
+
+    pub fn is_changeable(&self) -> bool {
+        self.sysctl_s.changeable
+    }
+
+    pub fn value_type(&self) -> &SysctlType {
+        &self.sysctl_s.value_type
+    }
+}
+
+/// Counters that can be subtracted to see how much they went up between
+/// two reads.
+pub trait Delta {
+    /// How much each counter has gone up since `earlier`. Counters that
+    /// wrapped around in between still come out right.
+    fn delta(&self, earlier: &Self) -> Self;
+}
+
+/// Reads a counter node over and over, keeping the last value around so
+/// each read can also say how much it changed.
+#[derive(Clone, Debug)]
+pub struct Sampler<T> {
+    handle: Handle,
+    last: Option<T>,
+}
+
+impl<T: SysctlValue + Delta + Clone> Sampler<T> {
+    pub fn new(name: &str) -> Result<Sampler<T>> {
+        Ok(Sampler { handle: Handle::new(name)?, last: None })
+    }
+
+    /// Read the node, handing back its value along with how much it went
+    /// up since the last sample. The first sample has no delta.
+    pub fn sample(&mut self) -> Result<(T, Option<T>)> {
+        let current = self.handle.get::<T>()?;
+        let delta = self.last.as_ref().map(|last| current.delta(last));
+        self.last = Some(current.clone());
+
+        Ok((current, delta))
+    }
+}
+
+/// Turn a numeric OID back into the dotted name the crate would resolve to
+/// it, e.g. `"kern.ostype"` for `[1, 1]`.
+pub fn mib_to_name(mib: &[c_int]) -> Result<String> {
+    KNOWN_NAMES.iter()
+        .find(|name| parse_mib_str(name).map(|s| s.mib == mib).unwrap_or(false))
+        .map(|name| name.to_string())
+        .ok_or_else(|| {
+            let ids: Vec<String> = mib.iter().map(|id| id.to_string()).collect();
+            SysctlError::UnknownName(ids.join("."))
+        })
+}
+
+/// Resolve a dotted name the way every call in the crate does.
+pub fn resolve(name: &str) -> Result<Sysctl> {
+    parse_mib_str(name)
+}
+
+/// The numeric OID a dotted name resolves to, e.g. `[1, 1]` for
+/// `kern.ostype`. Handy for checking the crate's mapping against sysctl(8).
+pub fn name_to_mib(name: &str) -> Result<Vec<c_int>> {
+    Ok(parse_mib_str(name)?.mib)
+}
+
+/// Any value a node can hold, for when the type isn't known until the name
+/// is resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Str(String),
+    /// Structs, and anything else without a more specific variant, as the
+    /// kernel handed them back.
+    Struct(Vec<u8>),
+    Node,
+    IntSlice(Vec<u32>),
+}
+
+impl fmt::Display for Value {
+    /// Format the way sysctl(8) would: numbers in decimal, strings as they
+    /// are, arrays comma-separated, and structs as hex bytes.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Struct(bytes) => {
+                for b in bytes {
+                    write!(f, "{:02x}", b)?;
+                }
+                Ok(())
+            },
+            Value::Node => write!(f, "<node>"),
+            Value::IntSlice(ints) => {
+                let ints: Vec<String> = ints.iter().map(|i| i.to_string()).collect();
+                write!(f, "{}", ints.join(","))
+            },
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Str(s) => serializer.serialize_str(s),
+            Value::Struct(bytes) => serializer.serialize_bytes(bytes),
+            Value::Node => serializer.serialize_unit(),
+            Value::IntSlice(ints) => serializer.collect_seq(ints),
+        }
+    }
+}
+
+/// Read `name` as whatever its type says it is.
+///
+/// The type-erased counterpart to `read`. Numeric nodes that come back a
+/// different size than their type suggests are handed back as `Struct`
+/// rather than failing.
+pub fn get_value(name: &str) -> Result<Value> {
+    let sysctl_s = parse_mib_str(name)?;
+    if sysctl_s.value_type == SysctlType::Node {
+        return Ok(Value::Node);
+    }
+
+    let buf = read::<Vec<u8>>(name)?;
+    let value = match sysctl_s.value_type {
+        SysctlType::Int32 | SysctlType::DevT if buf.len() == 4 => {
+            Value::Int(i64::from(i32::from_sysctl(&buf)?))
+        },
+        SysctlType::Int64 | SysctlType::Long if buf.len() == 8 => {
+            Value::Int(i64::from_sysctl(&buf)?)
+        },
+        SysctlType::SysString => Value::Str(String::from_sysctl(&buf)?),
+        SysctlType::UInt32Slice if buf.len() % 4 == 0 => {
+            Value::IntSlice(Vec::<u32>::from_sysctl(&buf)?)
+        },
+        SysctlType::UShortSlice if buf.len() % 2 == 0 => {
+            Value::IntSlice(buf.chunks(2)
+                .map(|chunk| u32::from(u16::from_ne_bytes([chunk[0], chunk[1]])))
+                .collect())
+        },
+        _ => Value::Struct(buf),
+    };
+
+    Ok(value)
+}
+
+/// Read every known leaf under `prefix` (`"hw"`, `"net.inet.tcp"`, or `""`
+/// for everything), like `sysctl -a`. Any leaf that can't be read or
+/// decoded, such as one only root can read or one this kernel doesn't have,
+/// is left out; only a prefix that matches no known name is an error.
+pub fn walk(prefix: &str) -> Result<Vec<(String, Value)>> {
+    let names: Vec<&str> = KNOWN_NAMES.iter()
+        .cloned()
+        .filter(|name| {
+            prefix.is_empty() || *name == prefix || name.starts_with(&format!("{}.", prefix))
+        })
+        .collect();
+    if names.is_empty() {
+        return Err(SysctlError::UnknownName(prefix.to_string()));
+    }
+
+    let mut values = Vec::new();
+    for name in names {
+        match get_value(name) {
+            Ok(Value::Node) => continue,
+            Ok(value) => values.push((name.to_string(), value)),
+            Err(_) => continue,
+        }
+    }
+
+    Ok(values)
+}
+
+// every fully-specified name the parser knows, for going from a MIB back to a
+// name; nodes that need extra arguments on the end (cp_time2, proc, route)
+// aren't in here
+const KNOWN_NAMES: &[&str] = &[
+    "kern.ostype", "kern.osrelease", "kern.osrevision", "kern.version",
+    "kern.maxvnodes", "kern.maxproc", "kern.maxfiles", "kern.argmax",
+    "kern.securelevel", "kern.hostname", "kern.hostid", "kern.clockrate",
+    "kern.profiling", "kern.posix1version", "kern.ngroups", "kern.job_control",
+    "kern.saved_ids", "kern.boottime", "kern.domainname", "kern.maxpartitions",
+    "kern.rawpartition", "kern.maxthread", "kern.nthreads", "kern.osversion",
+    "kern.somaxconn", "kern.sominconn", "kern.nosuidcoredump", "kern.fsync",
+    "kern.sysvmsg", "kern.sysvsem", "kern.sysvshm", "kern.msgbufsize",
+    "kern.malloc.bucket", "kern.malloc.buckets", "kern.malloc.kmemnames",
+    "kern.malloc.kmemstat", "kern.cp_time", "kern.nchstats", "kern.forkstat",
+    "kern.nselcoll", "kern.tty.tk_nin", "kern.tty.tk_nout", "kern.tty.tk_rawcc",
+    "kern.tty.tk_cancc", "kern.ccpu", "kern.fscale", "kern.nprocs",
+    "kern.msgbuf", "kern.pool", "kern.stackgap_random", "kern.sysvipc_info",
+    "kern.allowkmem", "kern.splassert", "kern.procargs", "kern.nfiles",
+    "kern.ttycount", "kern.numvnodes", "kern.mbstat", "kern.witness",
+    "kern.seminfo.semmni", "kern.seminfo.semmns", "kern.seminfo.semmsl",
+    "kern.seminfo.semopm", "kern.seminfo.semume", "kern.seminfo.semusz",
+    "kern.seminfo.semvmx", "kern.seminfo.semaem", "kern.shminfo.shmmax",
+    "kern.shminfo.shmmin", "kern.shminfo.shmmni", "kern.shminfo.shmseg",
+    "kern.shminfo.shmall", "kern.intrcnt", "kern.watchdog.period",
+    "kern.watchdog.auto", "kern.proc.all", "kern.maxclusters", "kern.evcount",
+    "kern.timecounter.tick", "kern.timecounter.timestepwarnings",
+    "kern.timecounter.hardware", "kern.timecounter.choice",
+    "kern.maxlocksperuid", "kern.bufcachepercent", "kern.file", "kern.wxabort",
+    "kern.consdev", "kern.netlivelocks", "kern.pool_debug", "kern.pool_cwd",
+    "kern.proc_nobroadcastkill", "kern.proc_vmmap", "kern.global_ptrace",
+    "kern.audio.record",
+    "vm.vmmeter", "vm.loadavg", "vm.psstrings", "vm.uvmexp", "vm.swapencrypt",
+    "vm.nkmempages", "vm.anonmin", "vm.vtextmin", "vm.vnodemin", "vm.maxslp",
+    "vm.uspace", "vm.malloc_conf",
+    "fs.posix.setuid",
+    "net.inet.ah.enable", "net.inet.ah.stats", "net.inet.bpf.bufsize",
+    "net.inet.bpf.maxbufsize", "net.inet.carp.allow", "net.inet.carp.log",
+    "net.inet.carp.preempt", "net.inet.carp.stats", "net.inet.divert.recvspace",
+    "net.inet.divert.sendspace", "net.inet.divert.stats", "net.inet.esp.enable",
+    "net.inet.esp.udpencap", "net.inet.esp.udpencap_port", "net.inet.esp.stats",
+    "net.inet.etherip.allow", "net.inet.etherip.stats", "net.inet.gre.allow",
+    "net.inet.gre.wccp", "net.inet.icmp.bmcastecho",
+    "net.inet.icmp.errppslimit", "net.inet.icmp.maskrepl",
+    "net.inet.icmp.rediraccept", "net.inet.icmp.redirtimeout",
+    "net.inet.icmp.stats", "net.inet.icmp.tstamprepl", "net.inet.ip.arpdown",
+    "net.inet.ip.arptimeout", "net.inet.ip.directed-broadcast",
+    "net.inet.ip.encdebug", "net.inet.ip.forwarding",
+    "net.inet.ip.ifq.congestion", "net.inet.ip.ifq.drops",
+    "net.inet.ip.ifq.len", "net.inet.ip.ifq.maxlen", "net.inet.ip.ipsec-allocs",
+    "net.inet.ip.ipsec-auth-alg", "net.inet.ip.ipsec-bytes",
+    "net.inet.ip.ipsec-comp-alg", "net.inet.ip.ipsec-enc-alg",
+    "net.inet.ip.ipsec-expire-acquire", "net.inet.ip.ipsec-firstuse", "net.inet.ip.ipsec-invalid-life",
+    "net.inet.ip.ipsec-pfs", "net.inet.ip.ipsec-soft-allocs",
+    "net.inet.ip.ipsec-soft-bytes", "net.inet.ip.ipsec-soft-firstuse",
+    "net.inet.ip.ipsec-soft-timeout", "net.inet.ip.ipsec-timeout",
+    "net.inet.ip.maxqueue", "net.inet.ip.mforwarding", "net.inet.ip.mtudisc",
+    "net.inet.ip.mtudisctimeout", "net.inet.ip.multipath",
+    "net.inet.ip.portfirst", "net.inet.ip.porthifirst",
+    "net.inet.ip.porthilast", "net.inet.ip.portlast", "net.inet.ip.redirect",
+    "net.inet.ip.sourceroute", "net.inet.ip.stats", "net.inet.ip.ttl",
+    "net.inet.ipcomp.enable", "net.inet.ipcomp.stats", "net.inet.ipip.allow",
+    "net.inet.ipip.stats", "net.inet.mobileip.allow", "net.inet.tcp.ackonpush",
+    "net.inet.tcp.always_keepalive", "net.inet.tcp.baddynamic",
+    "net.inet.tcp.drop", "net.inet.tcp.ecn", "net.inet.tcp.ident",
+    "net.inet.tcp.keepidle", "net.inet.tcp.keepinittime",
+    "net.inet.tcp.keepintvl", "net.inet.tcp.mssdflt", "net.inet.tcp.reasslimit",
+    "net.inet.tcp.recvspace", "net.inet.tcp.sendspace",
+    "net.inet.tcp.rfc1323", "net.inet.tcp.rfc3390", "net.inet.tcp.rootonly",
+    "net.inet.tcp.rstppslimit", "net.inet.tcp.sack",
+    "net.inet.tcp.sackholelimit", "net.inet.tcp.slowhz", "net.inet.tcp.stats",
+    "net.inet.tcp.synbucketlimit", "net.inet.tcp.syncachelimit",
+    "net.inet.tcp.synhashsize", "net.inet.tcp.synuselimit",
+    "net.inet.udp.baddynamic", "net.inet.udp.checksum",
+    "net.inet.udp.recvspace", "net.inet.udp.rootonly", "net.inet.udp.sendspace",
+    "net.inet.udp.stats",
+    "net.inet6.divert.recvspace", "net.inet6.divert.sendspace",
+    "net.inet6.divert.stats", "net.inet6.icmp6.errppslimit",
+    "net.inet6.icmp6.mtudisc_hiwat", "net.inet6.icmp6.mtudisc_lowat",
+    "net.inet6.icmp6.nd6_debug", "net.inet6.icmp6.nd6_delay",
+    "net.inet6.icmp6.nd6_maxnudhint", "net.inet6.icmp6.nd6_mmaxtries",
+    "net.inet6.icmp6.nd6_umaxtries", "net.inet6.icmp6.redirtimeout",
+    "net.inet6.ip6.auto_flowlabel", "net.inet6.ip6.dad_count",
+    "net.inet6.ip6.dad_pending", "net.inet6.ip6.defmcasthlim",
+    "net.inet6.ip6.forwarding", "net.inet6.ip6.hdrnestlimit",
+    "net.inet6.ip6.hlim", "net.inet6.ip6.ifq", "net.inet6.ip6.log_interval",
+    "net.inet6.ip6.maxdynroutes", "net.inet6.ip6.maxfragpackets",
+    "net.inet6.ip6.maxfrags", "net.inet6.ip6.mforwarding",
+    "net.inet6.ip6.mtudisctimeout", "net.inet6.ip6.multicast_mtudisc",
+    "net.inet6.ip6.multipath", "net.inet6.ip6.neighborgcthresh",
+    "net.inet6.ip6.redirect", "net.inet6.ip6.soiikey",
+    "net.inet6.ip6.use_deprecated",
+    "net.key.sadb_dump", "net.key.spd_dump",
+    "net.mpls.mapttl_ip", "net.mpls.mapttl_ip6", "net.mpls.maxloop_inkernel",
+    "net.mpls.ttl",
+    "net.pipex.enable", "net.pipex.inq.ifq.congestion",
+    "net.pipex.inq.ifq.drops", "net.pipex.inq.ifq.len",
+    "net.pipex.inq.ifq.maxlen", "net.pipex.outq.ifq.congestion",
+    "net.pipex.outq.ifq.drops", "net.pipex.outq.ifq.len",
+    "net.pipex.outq.ifq.maxlen",
+    "debug.name", "debug.value",
+    "hw.machine", "hw.model", "hw.ncpu", "hw.byteorder", "hw.pagesize",
+    "hw.disknames", "hw.diskstats", "hw.diskcount", "hw.sensors", "hw.cpuspeed",
+    "hw.setperf", "hw.vendor", "hw.product", "hw.version", "hw.serialno",
+    "hw.uuid", "hw.physmem", "hw.usermem", "hw.ncpufound", "hw.allowpowerdown",
+    "hw.perfpolicy", "hw.smt", "hw.ncpuonline",
+    "machdep.allowaperture", "machdep.kbdreset", "machdep.lidaction",
+    "machdep.pwraction",
+    "ddb.radix", "ddb.max_width", "ddb.max_line", "ddb.tab_stop_width",
+    "ddb.panic", "ddb.console", "ddb.log", "ddb.trigger", "ddb.profile",
+    "vfs.mounts", "vfs.ffs.max_softdeps", "vfs.ffs.sd_tickdelay",
+    "vfs.ffs.sd_worklist_push", "vfs.ffs.sd_blk_limit_push",
+    "vfs.ffs.sd_ino_limit_push", "vfs.ffs.sd_blk_limit_hit",
+    "vfs.ffs.sd_ino_limit_hit", "vfs.ffs.sd_sync_limit_hit",
+    "vfs.ffs.sd_indir_blk_ptrs", "vfs.ffs.sd_inode_bitmap",
+    "vfs.ffs.sd_direct_blk_ptrs", "vfs.ffs.sd_dir_entry",
+    "vfs.ffs.dirhash_dirsize", "vfs.ffs.dirhash_maxmem", "vfs.ffs.dirhash_mem",
+    "vfs.nfs.nfsstats", "vfs.nfs.iothreads", "vfs.mfs", "vfs.msdos", "vfs.ntfs",
+    "vfs.udf", "vfs.cd9660", "vfs.ext2fs", "vfs.fuse.fusefs_open_devices",
+    "vfs.fuse.fusefs_fbufs_in", "vfs.fuse.fusefs_fbufs_wait",
+    "vfs.fuse.fusefs_pool_pages",
+];
+
+/// Allocator statistics for one `malloc(9)` type (`struct kmemstats`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct KmemStats {
+    pub inuse: c_long,
+    pub calls: c_long,
+    pub memuse: c_long,
+    pub limblocks: u16,
+    pub mapblocks: u16,
+    pub maxused: c_long,
+    pub limit: c_long,
+    pub size: c_long,
+    spare: c_long,
+}
+
+sysctl_struct!(KmemStats);
+
+/// Allocator statistics for `malloc(9)` type `index`
+/// (`kern.malloc.kmemstat.N`). Types past the last one get
+/// `IndexOutOfRange`, with the number of type slots in `kern.malloc.kmemnames`
+/// as the limit.
+pub fn kmemstat(index: u32) -> Result<KmemStats> {
+    let name = format!("kern.malloc.kmemstat.{}", index);
+
+    match read(&name) {
+        Err(SysctlError::Os(Error::Sys(nix::errno::Errno::EINVAL))) => {
+            let max = read::<String>("kern.malloc.kmemnames")?
+                .trim_end_matches('\0')
+                .split(',')
+                .count();
+            if index as usize >= max {
+                return Err(SysctlError::IndexOutOfRange {
+                    name,
+                    index: index as usize,
+                    max,
+                });
+            }
+
+            Err(SysctlError::Os(Error::Sys(nix::errno::Errno::EINVAL)))
+        },
+        res => res,
+    }
+}
+
+/// The `malloc(9)` type numbers that are in use, with their names, from
+/// `kern.malloc.kmemnames`. The numbers are what `kmemstat` takes.
+pub fn kmemtypes() -> Result<Vec<(u32, String)>> {
+    Ok(parse_kmemnames(&read::<String>("kern.malloc.kmemnames")?))
+}
+
+/// The names of the `malloc(9)` types in use, in type number order.
+///
+/// The kernel leaves an empty slot for every unused type number and those
+/// are dropped here, so a name's position isn't its type number; use
+/// `kmemtypes` to line names up with `kmemstat`.
+pub fn kmemnames() -> Result<Vec<String>> {
+    Ok(kmemtypes()?.into_iter().map(|(_, name)| name).collect())
+}
+
+fn parse_kmemnames(names: &str) -> Vec<(u32, String)> {
+    names.split(',')
+        .enumerate()
+        .map(|(i, name)| (i as u32, name.trim_matches('\0')))
+        .filter(|&(_, name)| !name.is_empty())
+        .map(|(i, name)| (i, name.to_string()))
+        .collect()
+}
+
+/// Statistics for one pool(9) (`struct kinfo_pool`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PoolStats {
+    /// Size of an item, in bytes.
+    pub size: u32,
+    /// Size of a pool page, in bytes.
+    pub pgsize: u32,
+    pub itemsperpage: u32,
+    pub minpages: u32,
+    /// Most idle pages kept around.
+    pub maxpages: u32,
+    /// Most items that can ever be allocated.
+    pub hardlimit: u32,
+    pub npages: u32,
+    /// Items allocated right now.
+    pub nout: u32,
+    pub nitems: u32,
+    pub nget: c_ulong,
+    pub nput: c_ulong,
+    pub nfail: c_ulong,
+    pub npagealloc: c_ulong,
+    pub npagefree: c_ulong,
+    /// Most pages the pool has ever had.
+    pub hiwat: u32,
+    pub nidle: c_ulong,
+}
+
+sysctl_struct!(PoolStats);
+
+/// A pool(9), by its number and name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolInfo {
+    pub index: u32,
+    pub name: String,
+    pub stats: PoolStats,
+}
+
+// how many missing pool numbers in a row we take to mean there are no more
+const POOL_MAX_GAP: u32 = 64;
+
+/// Every pool in the kernel, like `vmstat -m`. Pools can come and go while
+/// we look, so this may find fewer than `kern.pool.npools` said there were.
+pub fn pools() -> Result<Vec<PoolInfo>> {
+    let npools = read::<i32>("kern.pool.npools")?.max(0) as usize;
+
+    // pools are numbered from 1, and ones that have been destroyed leave
+    // holes, so keep going until we've seen as many as there are or a long
+    // enough run of holes that there can't be any more
+    let mut pools = Vec::with_capacity(npools);
+    let mut index = 1;
+    let mut gap = 0;
+    while pools.len() < npools && gap < POOL_MAX_GAP {
+        let stats = read::<PoolStats>(&format!("kern.pool.{}", index));
+        // a pool destroyed between the two reads is just another hole
+        let entry = stats.and_then(|stats| {
+            read::<String>(&format!("kern.pool.name.{}", index)).map(|name| (name, stats))
+        });
+        match entry {
+            Ok((name, stats)) => {
+                pools.push(PoolInfo { index, name, stats });
+                gap = 0;
+            },
+            Err(SysctlError::Os(Error::Sys(nix::errno::Errno::ENOENT))) => gap += 1,
+            Err(e) => return Err(e),
+        }
+        index += 1;
+    }
+
+    Ok(pools)
+}
+
+// number of mbuf types, MT_*, struct mbstat counts
+const MBSTAT_NTYPES: usize = 256;
+
+/// mbuf allocation counters (`struct mbstat`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MbStat {
+    /// Times an allocation failed.
+    pub drops: c_ulong,
+    /// Times an allocation had to wait.
+    pub wait: c_ulong,
+    /// Times the protocols were drained to free some up.
+    pub drain: c_ulong,
+    /// mbufs allocated right now, by type (`MT_DATA`, `MT_HEADER`, ...).
+    pub mtypes: [c_ulong; MBSTAT_NTYPES],
+}
+
+impl Default for MbStat {
+    fn default() -> MbStat {
+        MbStat { drops: 0, wait: 0, drain: 0, mtypes: [0; MBSTAT_NTYPES] }
+    }
+}
+
+sysctl_struct!(MbStat);
+
+/// mbuf statistics (`kern.mbstat`).
+pub fn mbstat() -> Result<MbStat> {
+    read("kern.mbstat")
+}
+
+/// Clock rates (`struct clockinfo`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClockInfo {
+    pub hz: i32,
+    pub tick: i32,
+    pub stathz: i32,
+    pub profhz: i32,
+}
+
+sysctl_struct!(ClockInfo);
+
+impl ClockInfo {
+    /// Clock interrupts per second.
+    pub fn hz(&self) -> u32 {
+        self.hz.max(0) as u32
+    }
+
+    /// Length of one clock tick (`tick` is in microseconds).
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_micros(self.tick.max(0) as u64)
+    }
+}
+
+/// Clock rates (`kern.clockrate`).
+pub fn clockrate() -> Result<ClockInfo> {
+    read("kern.clockrate")
+}
+
+/// System-wide process and memory totals (`struct vmtotal`). Memory is
+/// counted in pages.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VmTotal {
+    /// Length of the run queue.
+    pub t_rq: u16,
+    /// Jobs in disk wait.
+    pub t_dw: u16,
+    /// Jobs in page wait.
+    pub t_pw: u16,
+    /// Jobs sleeping in core.
+    pub t_sl: u16,
+    /// Swapped out runnable or short block jobs.
+    pub t_sw: u16,
+    pub t_vm: u32,
+    pub t_avm: u32,
+    pub t_rm: u32,
+    pub t_arm: u32,
+    pub t_vmshr: u32,
+    pub t_avmshr: u32,
+    pub t_rmshr: u32,
+    pub t_armshr: u32,
+    pub t_free: u32,
+}
+
+sysctl_struct!(VmTotal);
+
+/// Process and memory totals (`vm.vmmeter`).
+pub fn vmmeter() -> Result<VmTotal> {
+    read("vm.vmmeter")
+}
+
+/// A device number split into its major and minor parts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DevNo {
+    pub major: u32,
+    pub minor: u32,
+    pub raw: dev_t,
+}
+
+impl DevNo {
+    /// Split `raw` the way OpenBSD's `major()` and `minor()` macros do.
+    pub fn from_raw(raw: dev_t) -> DevNo {
+        let dev = raw as u32;
+
+        DevNo {
+            major: (dev >> 8) & 0xff,
+            minor: (dev & 0xff) | ((dev & 0xffff_0000) >> 8),
+            raw,
+        }
+    }
+}
+
+impl SysctlValue for DevNo {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        let raw: dev_t = decode_struct(buf)?;
+
+        Ok(DevNo::from_raw(raw))
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        encode_struct(&self.raw)
+    }
+}
+
+/// The console device (`kern.consdev`).
+pub fn consdev() -> Result<DevNo> {
+    read("kern.consdev")
+}
+
+/// Physical memory in bytes (`hw.physmem`).
+pub fn physmem() -> Result<i64> {
+    read("hw.physmem")
+}
+
+/// Memory not wired down by the kernel, in bytes (`hw.usermem`).
+pub fn usermem() -> Result<u64> {
+    read("hw.usermem")
+}
+
+/// Virtual memory counters (`struct uvmexp`). Counts are in pages of
+/// `pagesize` bytes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UvmExp {
+    pub pagesize: i32,
+    pub pagemask: i32,
+    pub pageshift: i32,
+
+    pub npages: i32,
+    pub free: i32,
+    pub active: i32,
+    pub inactive: i32,
+    pub paging: i32,
+    pub wired: i32,
+
+    pub zeropages: i32,
+    pub reserve_pagedaemon: i32,
+    pub reserve_kernel: i32,
+    unused01: i32,
+    pub vnodepages: i32,
+    pub vtextpages: i32,
+
+    pub freemin: i32,
+    pub freetarg: i32,
+    pub inactarg: i32,
+    pub wiredmax: i32,
+    pub anonmin: i32,
+    pub vtextmin: i32,
+    pub vnodemin: i32,
+    pub anonminpct: i32,
+    pub vtextminpct: i32,
+    pub vnodeminpct: i32,
+
+    pub nswapdev: i32,
+    pub swpages: i32,
+    pub swpginuse: i32,
+    pub swpgonly: i32,
+    pub nswget: i32,
+    pub nanon: i32,
+    unused05: i32,
+    unused06: i32,
+
+    pub faults: i32,
+    pub traps: i32,
+    pub intrs: i32,
+    pub swtch: i32,
+    pub softs: i32,
+    pub syscalls: i32,
+    pub pageins: i32,
+    unused07: i32,
+    unused08: i32,
+    pub pgswapin: i32,
+    pub pgswapout: i32,
+    pub forks: i32,
+    pub forks_ppwait: i32,
+    pub forks_sharevm: i32,
+    pub pga_zerohit: i32,
+    pub pga_zeromiss: i32,
+    unused09: i32,
+
+    pub fltnoram: i32,
+    pub fltnoanon: i32,
+    pub fltnoamap: i32,
+    pub fltpgwait: i32,
+    pub fltpgrele: i32,
+    pub fltrelck: i32,
+    pub fltrelckok: i32,
+    pub fltanget: i32,
+    pub fltanretry: i32,
+    pub fltamcopy: i32,
+    pub fltnamap: i32,
+    pub fltnomap: i32,
+    pub fltlget: i32,
+    pub fltget: i32,
+    pub flt_anon: i32,
+    pub flt_acow: i32,
+    pub flt_obj: i32,
+    pub flt_prcopy: i32,
+    pub flt_przero: i32,
+
+    pub pdwoke: i32,
+    pub pdrevs: i32,
+    pub pdswout: i32,
+    pub pdfreed: i32,
+    pub pdscans: i32,
+    pub pdanscan: i32,
+    pub pdobscan: i32,
+    pub pdreact: i32,
+    pub pdbusy: i32,
+    pub pdpageouts: i32,
+    pub pdpending: i32,
+    pub pddeact: i32,
+    unused11: i32,
+    unused12: i32,
+    unused13: i32,
+
+    pub fpswtch: i32,
+    pub kmapent: i32,
+}
+
+impl UvmExp {
+    /// Free memory in bytes.
+    pub fn free_bytes(&self) -> u64 {
+        self.free.max(0) as u64 * self.pagesize.max(0) as u64
+    }
+}
+
+impl SysctlValue for UvmExp {
+    // the struct grows between releases, so take whatever overlaps with
+    // what we know about rather than insisting on an exact size
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        let mut uvmexp = UvmExp::default();
+        let len = buf.len().min(mem::size_of::<UvmExp>());
+        unsafe {
+            ptr::copy_nonoverlapping(buf.as_ptr(), &mut uvmexp as *mut UvmExp as *mut u8, len);
+        }
+
+        Ok(uvmexp)
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        encode_struct(self)
+    }
+}
+
+/// Virtual memory counters (`vm.uvmexp`), like `vmstat -s`.
+pub fn uvmexp() -> Result<UvmExp> {
+    read("vm.uvmexp")
+}
+
+// struct loadavg, as the kernel lays it out
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawLoadAvg {
+    ldavg: [u32; 3],
+    fscale: c_long,
+}
+
+/// The 1, 5 and 15 minute load averages (`vm.loadavg`).
+pub fn loadavg() -> Result<[f64; 3]> {
+    let raw: RawLoadAvg = decode_struct(&read::<Vec<u8>>("vm.loadavg")?)?;
+    if raw.fscale <= 0 {
+        return Err(SysctlError::InvalidValue {
+            name: "vm.loadavg".to_string(),
+            value: format!("fscale {}", raw.fscale),
+        });
+    }
+
+    let scale = raw.fscale as f64;
+
+    Ok([
+        f64::from(raw.ldavg[0]) / scale,
+        f64::from(raw.ldavg[1]) / scale,
+        f64::from(raw.ldavg[2]) / scale,
+    ])
+}
+
+/// What a memory widget wants to show, with page counts turned into bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryOverview {
+    pub physmem: u64,
+    pub usermem: u64,
+    pub free: u64,
+    pub active: u64,
+    pub inactive: u64,
+    pub loadavg: [f64; 3],
+}
+
+/// `hw.physmem`, `hw.usermem`, the page queues from `vm.uvmexp`, and
+/// `vm.loadavg` in one call.
+pub fn memory_overview() -> Result<MemoryOverview> {
+    let uvm = uvmexp()?;
+    let pages = |n: i32| n.max(0) as u64 * uvm.pagesize.max(0) as u64;
+
+    Ok(MemoryOverview {
+        physmem: physmem()?.max(0) as u64,
+        usermem: usermem()?,
+        free: pages(uvm.free),
+        active: pages(uvm.active),
+        inactive: pages(uvm.inactive),
+        loadavg: loadavg()?,
+    })
+}
+
+// malloc(3)'s option characters; the lowercase of each flag turns it back
+// off, and < and > halve and double the cache
+const MALLOC_OPTIONS: &str = "CcDdFfGgJjRrSsUuVvXx<>";
+
+/// The system-wide malloc(3) options (`vm.malloc_conf`), like `"S"`.
+pub fn malloc_conf() -> Result<String> {
+    read("vm.malloc_conf")
+}
+
+/// Set the system-wide malloc options. Any character malloc(3) doesn't
+/// know is refused as an `InvalidValue` before going to the kernel. Only
+/// root may do this.
+pub fn set_malloc_conf(conf: &str) -> Result<()> {
+    if !conf.chars().all(|c| MALLOC_OPTIONS.contains(c)) {
+        return Err(SysctlError::InvalidValue {
+            name: "vm.malloc_conf".to_string(),
+            value: conf.to_string(),
+        });
+    }
+
+    write("vm.malloc_conf", &conf.to_string())
+}
+
+/// Ticks spent in each CPU state, mirroring the kernel's `CP_*` indices.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpuTime {
+    pub user: u64,
+    pub nice: u64,
+    pub sys: u64,
+    pub spin: u64,
+    pub intr: u64,
+    pub idle: u64,
+}
+
+sysctl_struct!(CpuTime);
+
+impl Delta for CpuTime {
+    fn delta(&self, earlier: &CpuTime) -> CpuTime {
+        CpuTime {
+            user: self.user.wrapping_sub(earlier.user),
+            nice: self.nice.wrapping_sub(earlier.nice),
+            sys: self.sys.wrapping_sub(earlier.sys),
+            spin: self.spin.wrapping_sub(earlier.spin),
+            intr: self.intr.wrapping_sub(earlier.intr),
+            idle: self.idle.wrapping_sub(earlier.idle),
+        }
+    }
+}
+
+/// CPU times summed across every CPU (`kern.cp_time`).
+pub fn cp_time() -> Result<CpuTime> {
+    read("kern.cp_time")
+}
+
+/// CPU times for a single CPU (`kern.cp_time2.N`).
+///
+/// Asking for a CPU that doesn't exist gets you `IndexOutOfRange`, with
+/// `hw.ncpu` as the limit rather than `hw.ncpuonline`: CPUs the kernel has
+/// taken offline are still there to ask about.
+pub fn cp_time2(cpu: u32) -> Result<CpuTime> {
+    let name = format!("kern.cp_time2.{}", cpu);
+
+    match read(&name) {
+        Err(SysctlError::Os(e)) => {
+            let ncpu = read::<i32>("hw.ncpu")?.max(0) as u32;
+            if cpu >= ncpu {
+                return Err(SysctlError::IndexOutOfRange {
+                    name,
+                    index: cpu as usize,
+                    max: ncpu as usize,
+                });
+            }
+
+            Err(SysctlError::Os(e))
+        },
+        res => res,
+    }
+}
+
+/// CPU times for the whole machine.
+///
+/// The kernel keeps `kern.cp_time` as the sum of every CPU's
+/// `kern.cp_time2.N`, so this is a single read rather than one per CPU, and
+/// it doesn't race with CPUs going on or offline.
+pub fn cp_time_total() -> Result<CpuTime> {
+    cp_time()
+}
+
+// field names as sysctl(8) prints them under kern.nchstats
+const NCHSTATS_FIELDS: [&str; 12] = [
+    "good_hits", "negative_hits", "bad_hits", "false_hits", "misses",
+    "long_names", "pass2", "2passes", "ncs_revhits", "ncs_revmiss",
+    "ncs_dothits", "nch_dotdothits",
+];
+
+/// Name cache statistics (`struct nchstats`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NchStats {
+    pub good_hits: u64,
+    pub negative_hits: u64,
+    pub bad_hits: u64,
+    pub false_hits: u64,
+    pub misses: u64,
+    pub long_names: u64,
+    pub pass2: u64,
+    pub two_passes: u64,
+    pub rev_hits: u64,
+    pub rev_misses: u64,
+    pub dot_hits: u64,
+    pub dotdot_hits: u64,
+}
+
+sysctl_struct!(NchStats);
+
+impl NchStats {
+    /// Look up a counter by its sysctl(8) name, e.g. `"good_hits"`.
+    pub fn field(&self, name: &str) -> Option<u64> {
+        match name {
+            "good_hits" => Some(self.good_hits),
+            "negative_hits" => Some(self.negative_hits),
+            "bad_hits" => Some(self.bad_hits),
+            "false_hits" => Some(self.false_hits),
+            "misses" => Some(self.misses),
+            "long_names" => Some(self.long_names),
+            "pass2" => Some(self.pass2),
+            "2passes" => Some(self.two_passes),
+            "ncs_revhits" => Some(self.rev_hits),
+            "ncs_revmiss" => Some(self.rev_misses),
+            "ncs_dothits" => Some(self.dot_hits),
+            "nch_dotdothits" => Some(self.dotdot_hits),
+            _ => None,
+        }
+    }
+}
+
+/// Name cache statistics (`kern.nchstats`).
+pub fn nchstats() -> Result<NchStats> {
+    read("kern.nchstats")
+}
+
+// field names as sysctl(8) prints them under kern.forkstat
+const FORKSTAT_FIELDS: [&str; 8] = [
+    "forks", "vforks", "tforks", "kthreads", "fork_pages", "vfork_pages",
+    "tfork_pages", "kthread_pages",
+];
+
+/// Fork statistics (`struct forkstat`). The `*_pages` counters are VM pages
+/// affected by each kind of fork.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ForkStat {
+    pub forks: u64,
+    pub vforks: u64,
+    pub tforks: u64,
+    pub kthreads: u64,
+    pub fork_pages: u64,
+    pub vfork_pages: u64,
+    pub tfork_pages: u64,
+    pub kthread_pages: u64,
+}
+
+sysctl_struct!(ForkStat);
+
+impl ForkStat {
+    /// Look up a counter by its sysctl(8) name, e.g. `"vforks"`.
+    pub fn field(&self, name: &str) -> Option<u64> {
+        match name {
+            "forks" => Some(self.forks),
+            "vforks" => Some(self.vforks),
+            "tforks" => Some(self.tforks),
+            "kthreads" => Some(self.kthreads),
+            "fork_pages" => Some(self.fork_pages),
+            "vfork_pages" => Some(self.vfork_pages),
+            "tfork_pages" => Some(self.tfork_pages),
+            "kthread_pages" => Some(self.kthread_pages),
+            _ => None,
+        }
+    }
+}
+
+/// Fork statistics (`kern.forkstat`).
+pub fn forkstat() -> Result<ForkStat> {
+    read("kern.forkstat")
+}
+
+/// How many vnodes are in use against how many the kernel will allow.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VnodeUsage {
+    pub numvnodes: i32,
+    pub maxvnodes: i32,
+}
+
+impl VnodeUsage {
+    /// Fraction of `kern.maxvnodes` in use.
+    pub fn ratio(&self) -> f64 {
+        if self.maxvnodes <= 0 {
+            return 0.0;
+        }
+
+        f64::from(self.numvnodes) / f64::from(self.maxvnodes)
+    }
+}
+
+impl From<VnodeUsage> for (i32, i32) {
+    fn from(usage: VnodeUsage) -> (i32, i32) {
+        (usage.numvnodes, usage.maxvnodes)
+    }
+}
+
+/// `kern.numvnodes` and `kern.maxvnodes`, for keeping an eye on capacity.
+pub fn vnode_usage() -> Result<VnodeUsage> {
+    Ok(VnodeUsage {
+        numvnodes: read("kern.numvnodes")?,
+        maxvnodes: read("kern.maxvnodes")?,
+    })
+}
+
+/// TTY character counters from the `kern.tty` node.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TtyStats {
+    pub nin: u64,
+    pub nout: u64,
+    pub rawcc: u64,
+    pub cancc: u64,
+}
+
+/// TTY character counters (`kern.tty.tk_*`). Each counter is its own
+/// 64-bit leaf under `kern.tty`, so this makes one call per field.
+pub fn tty_stats() -> Result<TtyStats> {
+    Ok(TtyStats {
+        nin: read("kern.tty.tk_nin")?,
+        nout: read("kern.tty.tk_nout")?,
+        rawcc: read("kern.tty.tk_rawcc")?,
+        cancc: read("kern.tty.tk_cancc")?,
+    })
+}
+
+/// IP counters (`struct ipstat`). The kernel keeps these as `u_long`, which
+/// is 64 bits on amd64.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IpStat {
+    pub total: u64,
+    pub badsum: u64,
+    pub tooshort: u64,
+    pub toosmall: u64,
+    pub badhlen: u64,
+    pub badlen: u64,
+    pub fragments: u64,
+    pub fragdropped: u64,
+    pub fragtimeout: u64,
+    pub forward: u64,
+    pub cantforward: u64,
+    pub redirectsent: u64,
+    pub noproto: u64,
+    pub delivered: u64,
+    pub localout: u64,
+    pub odropped: u64,
+    pub reassembled: u64,
+    pub fragmented: u64,
+    pub ofragments: u64,
+    pub cantfrag: u64,
+    pub badoptions: u64,
+    pub noroute: u64,
+    pub badvers: u64,
+    pub rawout: u64,
+    pub badfrags: u64,
+    pub rcvmemdrop: u64,
+    pub toolong: u64,
+    pub nogif: u64,
+    pub badaddr: u64,
+    pub inswcsum: u64,
+    pub outswcsum: u64,
+    pub notmember: u64,
+    pub wrongif: u64,
+    pub idropped: u64,
+}
+
+sysctl_struct!(IpStat);
+
+/// IP counters (`net.inet.ip.stats`), like `netstat -s -p ip`.
+pub fn ip_stats() -> Result<IpStat> {
+    read("net.inet.ip.stats")
+}
+
+/// TCP counters (`struct tcpstat`), field for field in the same order as
+/// the kernel's header, including the SYN cache counters.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TcpStat {
+    pub connattempt: u32,
+    pub accepts: u32,
+    pub connects: u32,
+    pub drops: u32,
+    pub conndrops: u32,
+    pub closed: u32,
+    pub segstimed: u32,
+    pub rttupdated: u32,
+    pub delack: u32,
+    pub timeoutdrop: u32,
+    pub rexmttimeo: u32,
+    pub persisttimeo: u32,
+    pub persistdrop: u32,
+    pub keeptimeo: u32,
+    pub keepprobe: u32,
+    pub keepdrops: u32,
+    pub sndtotal: u32,
+    pub sndpack: u32,
+    pub sndbyte: u64,
+    pub sndrexmitpack: u32,
+    pub sndrexmitbyte: u64,
+    pub sndrexmitfast: u64,
+    pub sndacks: u32,
+    pub sndprobe: u32,
+    pub sndurg: u32,
+    pub sndwinup: u32,
+    pub sndctrl: u32,
+    pub rcvtotal: u32,
+    pub rcvpack: u32,
+    pub rcvbyte: u64,
+    pub rcvbadsum: u32,
+    pub rcvbadoff: u32,
+    pub rcvmemdrop: u32,
+    pub rcvnosec: u32,
+    pub rcvshort: u32,
+    pub rcvduppack: u32,
+    pub rcvdupbyte: u64,
+    pub rcvpartduppack: u32,
+    pub rcvpartdupbyte: u64,
+    pub rcvoopack: u32,
+    pub rcvoobyte: u64,
+    pub rcvpackafterwin: u32,
+    pub rcvbyteafterwin: u64,
+    pub rcvafterclose: u32,
+    pub rcvwinprobe: u32,
+    pub rcvdupack: u32,
+    pub rcvacktoomuch: u32,
+    pub rcvacktooold: u32,
+    pub rcvackpack: u32,
+    pub rcvackbyte: u64,
+    pub rcvwinupd: u32,
+    pub pawsdrop: u32,
+    pub predack: u32,
+    pub preddat: u32,
+    pub pcbhashmiss: u32,
+    pub noport: u32,
+    pub badsyn: u32,
+    pub dropsyn: u32,
+    pub rcvbadsig: u32,
+    pub rcvgoodsig: u64,
+    pub inswcsum: u32,
+    pub outswcsum: u32,
+    pub ecn_accepts: u32,
+    pub ecn_rcvece: u32,
+    pub ecn_rcvcwr: u32,
+    pub ecn_rcvce: u32,
+    pub ecn_sndect: u32,
+    pub ecn_sndece: u32,
+    pub ecn_sndcwr: u32,
+    pub cwr_ecn: u32,
+    pub cwr_frecovery: u32,
+    pub cwr_timeout: u32,
+    pub sc_added: u64,
+    pub sc_completed: u64,
+    pub sc_timed_out: u64,
+    pub sc_overflowed: u64,
+    pub sc_reset: u64,
+    pub sc_unreach: u64,
+    pub sc_bucketoverflow: u64,
+    pub sc_aborted: u64,
+    pub sc_dupesyn: u64,
+    pub sc_dropped: u64,
+    pub sc_collisions: u64,
+    pub sc_retransmitted: u64,
+    pub sc_seedrandom: u64,
+    pub sc_hash_size: u64,
+    pub sc_entry_count: u64,
+    pub sc_entry_limit: u64,
+    pub sc_bucket_maxlen: u64,
+    pub sc_bucket_limit: u64,
+    pub sc_uses_left: u64,
+    pub conndrained: u64,
+    pub sack_recovery_episode: u64,
+    pub sack_rexmits: u64,
+    pub sack_rexmit_bytes: u64,
+    pub sack_rcv_opts: u64,
+    pub sack_snd_opts: u64,
+    pub sack_drop_opts: u64,
+    pub outswtso: u32,
+    pub outhwtso: u32,
+    pub outpkttso: u32,
+    pub outbadtso: u32,
+    pub inswlro: u32,
+    pub inhwlro: u32,
+    pub inpktlro: u32,
+    pub inbadlro: u32,
+}
+
+sysctl_struct!(TcpStat);
+
+/// TCP counters (`net.inet.tcp.stats`), like `netstat -s -p tcp`.
+pub fn tcp_stats() -> Result<TcpStat> {
+    read("net.inet.tcp.stats")
+}
+
+/// UDP counters (`struct udpstat`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UdpStat {
+    pub ipackets: u64,
+    pub hdrops: u64,
+    pub badsum: u64,
+    pub nosum: u64,
+    pub badlen: u64,
+    pub noport: u64,
+    pub noportbcast: u64,
+    pub nosec: u64,
+    pub fullsock: u64,
+    pub pcbhashmiss: u64,
+    pub inswcsum: u64,
+    pub opackets: u64,
+    pub outswcsum: u64,
+}
+
+sysctl_struct!(UdpStat);
+
+/// UDP counters (`net.inet.udp.stats`).
+pub fn udp_stats() -> Result<UdpStat> {
+    read("net.inet.udp.stats")
+}
+
+/// ICMP counters (`struct icmpstat`). The histograms are indexed by ICMP
+/// message type.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IcmpStat {
+    pub error: u64,
+    pub toofreq: u64,
+    pub oldshort: u64,
+    pub oldicmp: u64,
+    pub outhist: [u64; ICMP_MAXTYPE + 1],
+    pub badcode: u64,
+    pub tooshort: u64,
+    pub checksum: u64,
+    pub badlen: u64,
+    pub reflect: u64,
+    pub bmcastecho: u64,
+    pub inhist: [u64; ICMP_MAXTYPE + 1],
+}
+
+// arrays this long don't derive Default
+impl Default for IcmpStat {
+    fn default() -> IcmpStat {
+        IcmpStat {
+            error: 0,
+            toofreq: 0,
+            oldshort: 0,
+            oldicmp: 0,
+            outhist: [0; ICMP_MAXTYPE + 1],
+            badcode: 0,
+            tooshort: 0,
+            checksum: 0,
+            badlen: 0,
+            reflect: 0,
+            bmcastecho: 0,
+            inhist: [0; ICMP_MAXTYPE + 1],
+        }
+    }
+}
+
+sysctl_struct!(IcmpStat);
+
+/// ICMP counters (`net.inet.icmp.stats`).
+pub fn icmp_stats() -> Result<IcmpStat> {
+    read("net.inet.icmp.stats")
+}
+
+/// The IP, TCP, UDP and ICMP counters together, for a `netstat -s` style
+/// summary. A protocol whose counters couldn't be read is `None`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InetStats {
+    pub ip: Option<IpStat>,
+    pub tcp: Option<TcpStat>,
+    pub udp: Option<UdpStat>,
+    pub icmp: Option<IcmpStat>,
+}
+
+/// Read every `net.inet.*.stats` we decode. One of them failing doesn't
+/// fail the rest.
+pub fn inet_stats() -> Result<InetStats> {
+    Ok(InetStats {
+        ip: ip_stats().ok(),
+        tcp: tcp_stats().ok(),
+        udp: udp_stats().ok(),
+        icmp: icmp_stats().ok(),
+    })
+}
+
+/// Divert socket counters (`struct divstat`, and the identical
+/// `struct div6stat`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DivStat {
+    pub ipackets: u64,
+    pub noport: u64,
+    pub fullsock: u64,
+    pub opackets: u64,
+    pub errors: u64,
+}
+
+sysctl_struct!(DivStat);
+
+/// Divert socket buffer sizes and counters for one address family.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DivertFamily {
+    pub recvspace: i32,
+    pub sendspace: i32,
+    pub stats: DivStat,
+}
+
+/// `net.inet.divert` and `net.inet6.divert` side by side.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DivertOverview {
+    pub inet: DivertFamily,
+    pub inet6: DivertFamily,
+}
+
+fn divert_family(prefix: &str) -> Result<DivertFamily> {
+    Ok(DivertFamily {
+        recvspace: read(&format!("{}.recvspace", prefix))?,
+        sendspace: read(&format!("{}.sendspace", prefix))?,
+        stats: read(&format!("{}.stats", prefix))?,
+    })
+}
+
+/// Divert socket settings and counters for both address families.
+pub fn divert_overview() -> Result<DivertOverview> {
+    Ok(DivertOverview {
+        inet: divert_family("net.inet.divert")?,
+        inet6: divert_family("net.inet6.divert")?,
+    })
+}
+
+/// AH counters (`struct ahstat`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AhStat {
+    pub hdrops: u64,
+    pub nopf: u64,
+    pub notdb: u64,
+    pub badkcr: u64,
+    pub badauth: u64,
+    pub noxform: u64,
+    pub qfull: u64,
+    pub wrap: u64,
+    pub replay: u64,
+    pub badauthl: u64,
+    pub input: u64,
+    pub output: u64,
+    pub invalid: u64,
+    pub ibytes: u64,
+    pub obytes: u64,
+    pub toobig: u64,
+    pub pdrops: u64,
+    pub crypto: u64,
+    pub outfail: u64,
+}
+
+/// ESP counters (`struct espstat`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EspStat {
+    pub hdrops: u64,
+    pub nopf: u64,
+    pub notdb: u64,
+    pub badkcr: u64,
+    pub qfull: u64,
+    pub noxform: u64,
+    pub badilen: u64,
+    pub wrap: u64,
+    pub badenc: u64,
+    pub badauth: u64,
+    pub replay: u64,
+    pub input: u64,
+    pub output: u64,
+    pub invalid: u64,
+    pub ibytes: u64,
+    pub obytes: u64,
+    pub toobig: u64,
+    pub pdrops: u64,
+    pub crypto: u64,
+    pub udpencin: u64,
+    pub udpencout: u64,
+    pub udpinval: u64,
+    pub udpneeded: u64,
+    pub outfail: u64,
+}
+
+/// CARP counters (`struct carpstats`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CarpStat {
+    pub ipackets: u64,
+    pub ipackets6: u64,
+    pub badif: u64,
+    pub badttl: u64,
+    pub hdrops: u64,
+    pub badsum: u64,
+    pub badver: u64,
+    pub badlen: u64,
+    pub badauth: u64,
+    pub badvhid: u64,
+    pub badaddrs: u64,
+    pub opackets: u64,
+    pub opackets6: u64,
+    pub onomem: u64,
+    pub ostates: u64,
+    pub preempt: u64,
+}
+
+sysctl_struct!(AhStat, EspStat, CarpStat);
+
+/// AH counters (`net.inet.ah.stats`).
+pub fn read_ahstat() -> Result<AhStat> {
+    read("net.inet.ah.stats")
+}
+
+/// ESP counters (`net.inet.esp.stats`).
+pub fn read_espstat() -> Result<EspStat> {
+    read("net.inet.esp.stats")
+}
+
+/// CARP counters (`net.inet.carp.stats`).
+pub fn read_carpstat() -> Result<CarpStat> {
+    read("net.inet.carp.stats")
+}
+
+/// The same as `read_carpstat`, named after the node.
+pub fn carp_stats() -> Result<CarpStat> {
+    read_carpstat()
+}
+
+/// A process table entry (`struct kinfo_proc`), with fields like `p_pid`,
+/// `p_comm` and `p_vm_rssize`.
+pub type KinfoProc = libc::kinfo_proc;
+
+sysctl_struct!(KinfoProc);
+
+pub type KinfoFile = libc::kinfo_file;
+
+sysctl_struct!(KinfoFile);
+
+fn read_kinfo_files(name: &str) -> Result<Vec<KinfoFile>> {
+    let buf = read::<Vec<u8>>(name)?;
+    let size = mem::size_of::<KinfoFile>();
+    if buf.len() % size != 0 {
+        let expected = (buf.len() / size + 1) * size;
+        return Err(SysctlError::BadLength { expected, got: buf.len() });
+    }
+
+    buf.chunks(size).map(KinfoFile::from_sysctl).collect()
+}
+
+/// Every open file on the system (`kern.file`), like `fstat`. The
+/// interesting fields are `fd_fd`, `f_type`, `f_flag` and `va_fileid`.
+pub fn files() -> Result<Vec<KinfoFile>> {
+    read_kinfo_files("kern.file")
+}
+
+/// The files process `pid` has open (`kern.file.pid.N`).
+pub fn files_by_pid(pid: pid_t) -> Result<Vec<KinfoFile>> {
+    read_kinfo_files(&format!("kern.file.pid.{}", pid))
+}
+
+// the kernel hands back a NULL-terminated array of pointers into the buffer,
+// followed by the strings themselves, one after the other
+fn parse_proc_args(buf: &[u8]) -> Result<Vec<String>> {
+    let ptr_size = mem::size_of::<*const c_char>();
+    let count = buf.chunks_exact(ptr_size)
+        .position(|p| p.iter().all(|&b| b == 0))
+        .ok_or_else(|| SysctlError::BadLength { expected: buf.len() + ptr_size, got: buf.len() })?;
+
+    let strings = &buf[(count + 1) * ptr_size..];
+    let args: Vec<String> = strings.split(|&b| b == 0)
+        .take(count)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    if args.len() < count {
+        return Err(SysctlError::BadLength { expected: buf.len() + 1, got: buf.len() });
+    }
+
+    Ok(args)
+}
+
+fn proc_args(pid: pid_t, which: &str) -> Result<Vec<String>> {
+    let buf = read::<Vec<u8>>(&format!("kern.procargs.{}.{}", pid, which))?;
+
+    // the process went away between sizing the buffer and filling it
+    if buf.is_empty() {
+        return Err(Error::Sys(nix::errno::Errno::ESRCH).into());
+    }
+
+    parse_proc_args(&buf)
+}
+
+/// The command line process `pid` was started with
+/// (`kern.procargs.N.argv`).
+pub fn proc_argv(pid: pid_t) -> Result<Vec<String>> {
+    proc_args(pid, "argv")
+}
+
+/// The environment of process `pid` (`kern.procargs.N.env`), as
+/// `NAME=value` strings.
+pub fn proc_env(pid: pid_t) -> Result<Vec<String>> {
+    proc_args(pid, "env")
+}
+
+/// One mapped region of a process's address space.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VmEntry {
+    pub start: u64,
+    pub end: u64,
+    pub offset: u64,
+    /// `PROT_*` bits.
+    pub protection: i32,
+    pub max_protection: i32,
+    pub wired_count: i32,
+    /// `KVE_ET_*` bits.
+    pub etype: i32,
+    /// `KVE_F_*` bits.
+    pub flags: u8,
+}
+
+impl From<kinfo_vmentry> for VmEntry {
+    fn from(kve: kinfo_vmentry) -> VmEntry {
+        VmEntry {
+            start: kve.kve_start,
+            end: kve.kve_end,
+            offset: kve.kve_offset,
+            protection: kve.kve_protection,
+            max_protection: kve.kve_max_protection,
+            wired_count: kve.kve_wired_count,
+            etype: kve.kve_etype,
+            flags: kve.kve_flags,
+        }
+    }
+}
+
+// how many regions to ask for at a time
+const VMMAP_BATCH: usize = 64;
+
+/// The memory map of process `pid` (`kern.proc_vmmap.N`), like `procmap`.
+pub fn proc_vmmap(pid: pid_t) -> Result<Vec<VmEntry>> {
+    let sysctl_s = parse_mib_str(&format!("kern.proc_vmmap.{}", pid))?;
+    let size = mem::size_of::<kinfo_vmentry>();
+    let mut kves: Vec<kinfo_vmentry> = vec![unsafe { mem::zeroed() }; VMMAP_BATCH];
+    let mut entries = Vec::new();
+
+    // the kernel fills in regions starting from the kve_start we hand it
+    // in the first slot, and says ENOMEM when there are more than fit, so
+    // keep asking again from where the last batch ended
+    let mut next = 0;
+    loop {
+        kves[0].kve_start = next;
+        let mut len = VMMAP_BATCH * size;
+        let res = unsafe {
+            libc::sysctl(sysctl_s.mib.as_ptr(),
+                         sysctl_s.mib.len() as u32,
+                         kves.as_mut_ptr() as *mut c_void,
+                         &mut len,
+                         ptr::null_mut(),
+                         0)
+        };
+
+        let more = if res < 0 {
+            let e = last_os_error();
+            if e != Error::Sys(nix::errno::Errno::ENOMEM) {
+                return Err(e.into());
+            }
+            true
+        } else {
+            false
+        };
+
+        let n = len / size;
+        entries.extend(kves[..n].iter().map(|&kve| VmEntry::from(kve)));
+        if !more || n == 0 {
+            break;
+        }
+        next = kves[n - 1].kve_end;
+    }
+
+    Ok(entries)
+}
+
+/// Look up a single process (`kern.proc.pid.N`).
+pub fn proc_by_pid(pid: pid_t) -> Result<KinfoProc> {
+    let buf = read::<Vec<u8>>(&format!("kern.proc.pid.{}", pid))?;
+
+    // a pid that doesn't exist isn't an error to the kernel, we just get
+    // nothing back
+    if buf.is_empty() {
+        return Err(Error::Sys(nix::errno::Errno::ESRCH).into());
+    }
+
+    KinfoProc::from_sysctl(&buf)
+}
+
+/// How long IPsec SAs may live after their first use, hard and soft limits.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IpsecConfig {
+    /// `net.inet.ip.ipsec-firstuse`
+    pub firstuse: Duration,
+    /// `net.inet.ip.ipsec-soft-firstuse`, which has to be no longer than
+    /// `firstuse`
+    pub soft_firstuse: Duration,
+}
+
+/// `net.inet.ip.ipsec-firstuse`
+pub fn ipsec_firstuse() -> Result<Duration> {
+    read_secs("net.inet.ip.ipsec-firstuse")
+}
+
+/// Set `net.inet.ip.ipsec-firstuse`.
+pub fn set_ipsec_firstuse(limit: Duration) -> Result<()> {
+    write_secs("net.inet.ip.ipsec-firstuse", limit)
+}
+
+/// `net.inet.ip.ipsec-soft-firstuse`
+pub fn ipsec_soft_firstuse() -> Result<Duration> {
+    read_secs("net.inet.ip.ipsec-soft-firstuse")
+}
+
+/// Set `net.inet.ip.ipsec-soft-firstuse`.
+pub fn set_ipsec_soft_firstuse(limit: Duration) -> Result<()> {
+    write_secs("net.inet.ip.ipsec-soft-firstuse", limit)
+}
+
+/// Read the IPsec lifetime settings in one go.
+pub fn ipsec_config() -> Result<IpsecConfig> {
+    Ok(IpsecConfig {
+        firstuse: ipsec_firstuse()?,
+        soft_firstuse: ipsec_soft_firstuse()?,
+    })
+}
+
+/// Write the IPsec lifetime settings, refusing a soft limit longer than the
+/// hard one as an `InvalidValue` before touching anything.
+pub fn set_ipsec_config(config: &IpsecConfig) -> Result<()> {
+    if config.soft_firstuse > config.firstuse {
+        return Err(SysctlError::InvalidValue {
+            name: "net.inet.ip.ipsec-soft-firstuse".to_string(),
+            value: config.soft_firstuse.as_secs().to_string(),
+        });
+    }
+
+    set_ipsec_firstuse(config.firstuse)?;
+    set_ipsec_soft_firstuse(config.soft_firstuse)
+}
+
+/// The IPsec settings worth seeing at a glance.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IpsecOverview {
+    pub enc_alg: String,
+    pub auth_alg: String,
+    pub comp_alg: String,
+    pub timeout: Duration,
+    pub soft_timeout: Duration,
+    pub firstuse: Duration,
+    pub soft_firstuse: Duration,
+    pub pfs: bool,
+}
+
+/// Default algorithms, SA lifetimes, and whether PFS is on, in one call.
+pub fn ipsec_overview() -> Result<IpsecOverview> {
+    Ok(IpsecOverview {
+        enc_alg: read("net.inet.ip.ipsec-enc-alg")?,
+        auth_alg: read("net.inet.ip.ipsec-auth-alg")?,
+        comp_alg: read("net.inet.ip.ipsec-comp-alg")?,
+        timeout: read_secs("net.inet.ip.ipsec-timeout")?,
+        soft_timeout: read_secs("net.inet.ip.ipsec-soft-timeout")?,
+        firstuse: ipsec_firstuse()?,
+        soft_firstuse: ipsec_soft_firstuse()?,
+        pfs: read("net.inet.ip.ipsec-pfs")?,
+    })
+}
+
+// int nodes that count seconds
+fn read_secs(name: &str) -> Result<Duration> {
+    let secs = read::<i32>(name)?;
+    if secs < 0 {
+        return Err(SysctlError::InvalidValue { name: name.to_string(), value: secs.to_string() });
+    }
+
+    Ok(Duration::from_secs(secs as u64))
+}
+
+fn write_secs(name: &str, value: Duration) -> Result<()> {
+    if value.as_secs() > i32::MAX as u64 {
+        return Err(SysctlError::InvalidValue {
+            name: name.to_string(),
+            value: value.as_secs().to_string(),
+        });
+    }
+
+    write(name, &(value.as_secs() as i32))
+}
+
+/// The changeable knobs under `net.inet.tcp`. Timers are in slow-timeout
+/// ticks (`net.inet.tcp.slowhz` of them a second).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TcpTuning {
+    pub rfc1323: bool,
+    pub rfc3390: bool,
+    pub sack: bool,
+    pub ecn: bool,
+    pub mssdflt: i32,
+    pub ackonpush: bool,
+    pub always_keepalive: bool,
+    pub keepinittime: i32,
+    pub keepidle: i32,
+    pub keepintvl: i32,
+}
+
+/// Read every TCP tuning knob in one go.
+pub fn read_tcp_tuning() -> Result<TcpTuning> {
+    Ok(TcpTuning {
+        rfc1323: read("net.inet.tcp.rfc1323")?,
+        rfc3390: read("net.inet.tcp.rfc3390")?,
+        sack: read("net.inet.tcp.sack")?,
+        ecn: read("net.inet.tcp.ecn")?,
+        mssdflt: read("net.inet.tcp.mssdflt")?,
+        ackonpush: read("net.inet.tcp.ackonpush")?,
+        always_keepalive: read("net.inet.tcp.always_keepalive")?,
+        keepinittime: read("net.inet.tcp.keepinittime")?,
+        keepidle: read("net.inet.tcp.keepidle")?,
+        keepintvl: read("net.inet.tcp.keepintvl")?,
+    })
+}
+
+/// Write every TCP tuning knob, carrying on past failures. Each node's name
+/// comes back alongside how writing it went.
+pub fn set_tcp_tuning(tuning: &TcpTuning) -> Vec<(&'static str, Result<()>)> {
+    vec![
+        write_named("net.inet.tcp.rfc1323", &tuning.rfc1323),
+        write_named("net.inet.tcp.rfc3390", &tuning.rfc3390),
+        write_named("net.inet.tcp.sack", &tuning.sack),
+        write_named("net.inet.tcp.ecn", &tuning.ecn),
+        write_named("net.inet.tcp.mssdflt", &tuning.mssdflt),
+        write_named("net.inet.tcp.ackonpush", &tuning.ackonpush),
+        write_named("net.inet.tcp.always_keepalive", &tuning.always_keepalive),
+        write_named("net.inet.tcp.keepinittime", &tuning.keepinittime),
+        write_named("net.inet.tcp.keepidle", &tuning.keepidle),
+        write_named("net.inet.tcp.keepintvl", &tuning.keepintvl),
+    ]
+}
+
+fn write_named<T: SysctlValue>(name: &'static str, value: &T) -> (&'static str, Result<()>) {
+    (name, write(name, value))
+}
+
+/// Current CPU speed in MHz (`hw.cpuspeed`).
+pub fn cpuspeed_mhz() -> Result<u32> {
+    let mhz = read::<i32>("hw.cpuspeed")?;
+    if mhz < 0 {
+        return Err(SysctlError::InvalidValue {
+            name: "hw.cpuspeed".to_string(),
+            value: mhz.to_string(),
+        });
+    }
+
+    Ok(mhz as u32)
+}
+
+/// Like `cpuspeed_mhz`, as the `int` the kernel keeps it in.
+pub fn cpuspeed() -> Result<i32> {
+    Ok(cpuspeed_mhz()? as i32)
+}
+
+/// Current CPU speed in MHz along with the `hw.setperf` percentage it's
+/// scaled to. Only works on machines that support `hw.setperf`.
+pub fn cpu_scaling() -> Result<(u32, u8)> {
+    let perf = read::<i32>("hw.setperf")?;
+    if !(0..=100).contains(&perf) {
+        return Err(SysctlError::InvalidValue {
+            name: "hw.setperf".to_string(),
+            value: perf.to_string(),
+        });
+    }
+
+    Ok((cpuspeed_mhz()?, perf as u8))
+}
+
+/// The performance level the CPU is set to, as a percentage
+/// (`hw.setperf`).
+pub fn setperf() -> Result<i32> {
+    read("hw.setperf")
+}
+
+/// Set the CPU performance level, 0 to 100; anything else is an
+/// `InvalidValue`. Only root may do this.
+pub fn set_setperf(pct: i32) -> Result<()> {
+    if !(0..=100).contains(&pct) {
+        return Err(SysctlError::InvalidValue {
+            name: "hw.setperf".to_string(),
+            value: pct.to_string(),
+        });
+    }
+
+    write("hw.setperf", &pct)
+}
+
+// what hw.perfpolicy will take
+const PERF_POLICIES: &[&str] = &["manual", "auto", "high"];
+
+/// How the CPU speed is managed (`hw.perfpolicy`): `"manual"` leaves it at
+/// whatever `hw.setperf` says, `"auto"` scales it with load and `"high"`
+/// keeps it at full speed.
+pub fn perfpolicy() -> Result<String> {
+    read("hw.perfpolicy")
+}
+
+/// Set the performance policy. A policy the kernel doesn't know gets an
+/// `UnknownName` without going to the kernel. Only root may do this.
+pub fn set_perfpolicy(policy: &str) -> Result<()> {
+    if !PERF_POLICIES.contains(&policy) {
+        return Err(SysctlError::UnknownName(policy.to_string()));
+    }
+
+    write("hw.perfpolicy", &policy.to_string())
+}
+
+/// The machine's byte order, as reported by `hw.byteorder`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl SysctlValue for ByteOrder {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        match i32::from_sysctl(buf)? {
+            1234 => Ok(ByteOrder::Little),
+            4321 => Ok(ByteOrder::Big),
+            order => Err(SysctlError::InvalidValue {
+                name: "hw.byteorder".to_string(),
+                value: order.to_string(),
+            }),
+        }
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        match self {
+            ByteOrder::Little => 1234i32.to_sysctl(),
+            ByteOrder::Big => 4321i32.to_sysctl(),
+        }
+    }
+}
+
+/// Whether this machine is little- or big-endian.
+pub fn byteorder() -> Result<ByteOrder> {
+    read("hw.byteorder")
+}
+
+/// Number of CPUs the kernel is using (`hw.ncpu`).
+pub fn ncpu() -> Result<u32> {
+    read_cpu_count("hw.ncpu")
+}
+
+/// Number of CPUs that are online (`hw.ncpuonline`), which can be fewer
+/// than were found, e.g. with SMT turned off.
+pub fn ncpu_online() -> Result<u32> {
+    read_cpu_count("hw.ncpuonline")
+}
+
+/// Number of CPUs found at boot (`hw.ncpufound`).
+pub fn ncpu_found() -> Result<u32> {
+    read_cpu_count("hw.ncpufound")
+}
+
+fn read_cpu_count(name: &str) -> Result<u32> {
+    let n = read::<i32>(name)?;
+    if n < 0 {
+        return Err(SysctlError::InvalidValue { name: name.to_string(), value: n.to_string() });
+    }
+
+    Ok(n as u32)
+}
+
+/// What closing the lid does (`machdep.lidaction`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LidAction {
+    Ignore,
+    Suspend,
+    Hibernate,
+    /// A value this crate doesn't know about.
+    Unknown(i32),
+}
+
+impl LidAction {
+    pub fn from_i32(v: i32) -> LidAction {
+        match v {
+            0 => LidAction::Ignore,
+            1 => LidAction::Suspend,
+            2 => LidAction::Hibernate,
+            v => LidAction::Unknown(v),
+        }
+    }
+
+    pub fn to_i32(&self) -> i32 {
+        match *self {
+            LidAction::Ignore => 0,
+            LidAction::Suspend => 1,
+            LidAction::Hibernate => 2,
+            LidAction::Unknown(v) => v,
+        }
+    }
+}
+
+impl SysctlValue for LidAction {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        Ok(LidAction::from_i32(i32::from_sysctl(buf)?))
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        self.to_i32().to_sysctl()
+    }
+}
+
+/// What pressing the power button does (`machdep.pwraction`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerAction {
+    Ignore,
+    Powerdown,
+    Suspend,
+    Hibernate,
+    /// A value this crate doesn't know about.
+    Unknown(i32),
+}
+
+impl PowerAction {
+    pub fn from_i32(v: i32) -> PowerAction {
+        match v {
+            0 => PowerAction::Ignore,
+            1 => PowerAction::Powerdown,
+            2 => PowerAction::Suspend,
+            3 => PowerAction::Hibernate,
+            v => PowerAction::Unknown(v),
+        }
+    }
+
+    pub fn to_i32(&self) -> i32 {
+        match *self {
+            PowerAction::Ignore => 0,
+            PowerAction::Powerdown => 1,
+            PowerAction::Suspend => 2,
+            PowerAction::Hibernate => 3,
+            PowerAction::Unknown(v) => v,
+        }
+    }
+}
+
+impl SysctlValue for PowerAction {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        Ok(PowerAction::from_i32(i32::from_sysctl(buf)?))
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        self.to_i32().to_sysctl()
+    }
+}
+
+/// What closing the lid currently does.
+pub fn lidaction() -> Result<LidAction> {
+    read("machdep.lidaction")
+}
+
+/// Set what closing the lid does. `Unknown` values are refused as an
+/// `InvalidValue`, since the kernel won't take them either. Only root may do
+/// this.
+pub fn set_lidaction(action: LidAction) -> Result<()> {
+    if let LidAction::Unknown(v) = action {
+        return Err(SysctlError::InvalidValue {
+            name: "machdep.lidaction".to_string(),
+            value: v.to_string(),
+        });
+    }
+
+    write("machdep.lidaction", &action)
+}
+
+/// What pressing the power button currently does.
+pub fn pwraction() -> Result<PowerAction> {
+    read("machdep.pwraction")
+}
+
+/// Like `set_lidaction`, for the power button.
+pub fn set_pwraction(action: PowerAction) -> Result<()> {
+    if let PowerAction::Unknown(v) = action {
+        return Err(SysctlError::InvalidValue {
+            name: "machdep.pwraction".to_string(),
+            value: v.to_string(),
+        });
+    }
+
+    write("machdep.pwraction", &action)
+}
+
+/// How much access X gets to the graphics aperture
+/// (`machdep.allowaperture`): 0 for none, 1 for the VGA framebuffer and
+/// 2 for all of it.
+pub fn allowaperture() -> Result<i32> {
+    read("machdep.allowaperture")
+}
+
+/// Set `machdep.allowaperture` to 0, 1 or 2. The kernel only lets this be
+/// raised while the securelevel is still 0 or lower, i.e. in practice from
+/// `/etc/sysctl.conf` at boot, and refuses it with `PermissionDenied` after
+/// that.
+pub fn set_allowaperture(v: i32) -> Result<()> {
+    if !(0..=2).contains(&v) {
+        return Err(SysctlError::InvalidValue {
+            name: "machdep.allowaperture".to_string(),
+            value: v.to_string(),
+        });
+    }
+
+    write("machdep.allowaperture", &v)
+}
+
+/// The OS release as `(major, minor)`, e.g. `(7, 5)`, for comparing
+/// against: `if osrelease()? >= (7, 4)`.
+pub fn osrelease() -> Result<(u32, u32)> {
+    parse_osrelease(&osrelease_str()?)
+}
+
+/// `kern.osrelease` as the kernel has it, e.g. `"7.5"`.
+pub fn osrelease_str() -> Result<String> {
+    read("kern.osrelease")
+}
+
+fn parse_osrelease(s: &str) -> Result<(u32, u32)> {
+    let mut parts = s.trim_end_matches('\0').splitn(2, '.');
+    match (parts.next().map(str::parse), parts.next().map(str::parse)) {
+        (Some(Ok(major)), Some(Ok(minor))) => Ok((major, minor)),
+        _ => Err(SysctlError::InvalidValue {
+            name: "kern.osrelease".to_string(),
+            value: s.to_string(),
+        }),
+    }
+}
+
+/// What `kern.version` says about the running kernel. Anything that
+/// couldn't be made out is `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KernelVersion {
+    /// E.g. `"OpenBSD"`.
+    pub os: Option<String>,
+    /// E.g. `"7.5"` or `"7.5-current"`.
+    pub release: Option<String>,
+    /// The kernel config it was built from, e.g. `"GENERIC.MP"`.
+    pub kernel_config: Option<String>,
+    pub build_number: Option<u32>,
+    pub build_date: Option<String>,
+    /// Who built it and where, e.g. `"deraadt@amd64.openbsd.org"`.
+    pub builder: Option<String>,
+}
+
+/// The running kernel's version, parsed out of `kern.version`.
+pub fn version() -> Result<KernelVersion> {
+    Ok(parse_kernel_version(&read::<String>("kern.version")?))
+}
+
+// the kernel has it as
+// "OpenBSD 7.5 (GENERIC.MP) #82: Wed Mar 20 15:48:40 MDT 2024\n
+//     deraadt@amd64.openbsd.org:/usr/src/sys/arch/amd64/compile/GENERIC.MP\n"
+fn parse_kernel_version(s: &str) -> KernelVersion {
+    let mut version = KernelVersion::default();
+    let mut lines = s.lines();
+
+    if let Some(line) = lines.next() {
+        let (head, date) = match line.find(": ") {
+            Some(i) => (&line[..i], Some(line[i + 2..].trim())),
+            None => (line, None),
+        };
+        let mut words = head.split_whitespace();
+        version.os = words.next().map(|w| w.to_string());
+        version.release = words.next().map(|w| w.to_string());
+        version.kernel_config = words.next()
+            .filter(|w| w.starts_with('(') && w.ends_with(')') && w.len() > 2)
+            .map(|w| w[1..w.len() - 1].to_string());
+        version.build_number = words.next()
+            .filter(|w| w.starts_with('#'))
+            .and_then(|w| w[1..].parse().ok());
+        version.build_date = date.filter(|d| !d.is_empty()).map(|d| d.to_string());
+    }
+
+    if let Some(line) = lines.next() {
+        let builder = line.trim().split(':').next().unwrap_or("");
+        if builder.contains('@') {
+            version.builder = Some(builder.to_string());
+        }
+    }
+
+    version
+}
+
+/// When the system booted (`kern.boottime`).
+pub fn boottime() -> Result<SystemTime> {
+    let tv = decode_struct::<timeval>(&read::<Vec<u8>>("kern.boottime")?)?;
+
+    Ok(UNIX_EPOCH + timeval_to_duration(&tv))
+}
+
+/// How long it's been since boot. If the clock has been set back to before
+/// the boot time, this is zero.
+pub fn uptime() -> Result<Duration> {
+    Ok(SystemTime::now().duration_since(boottime()?).unwrap_or(Duration::ZERO))
+}
+
+/// A timecounter the kernel could keep time with, and how good it thinks
+/// it is. Higher quality is better; negative means it's never picked on
+/// its own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimecounterChoice {
+    pub name: String,
+    pub quality: i32,
+}
+
+/// The timecounter the kernel is keeping time with
+/// (`kern.timecounter.hardware`).
+pub fn timecounter_hardware() -> Result<String> {
+    read("kern.timecounter.hardware")
+}
+
+/// Switch to another timecounter. It has to be one of
+/// `timecounter_choices`, or this fails with `InvalidValue` without going
+/// to the kernel. Only root may do this.
+pub fn set_timecounter_hardware(name: &str) -> Result<()> {
+    if !timecounter_choices()?.iter().any(|c| c.name == name) {
+        return Err(SysctlError::InvalidValue {
+            name: "kern.timecounter.hardware".to_string(),
+            value: name.to_string(),
+        });
+    }
+
+    write("kern.timecounter.hardware", &name.to_string())
+}
+
+/// Every timecounter there is to choose from (`kern.timecounter.choice`).
+pub fn timecounter_choices() -> Result<Vec<TimecounterChoice>> {
+    parse_timecounter_choices(&read::<String>("kern.timecounter.choice")?)
+}
+
+// the kernel lists them as "i8254(0) tsc(2000) dummy(-1000000)"
+fn parse_timecounter_choices(s: &str) -> Result<Vec<TimecounterChoice>> {
+    s.split_whitespace()
+        .map(|entry| {
+            let invalid = || SysctlError::InvalidValue {
+                name: "kern.timecounter.choice".to_string(),
+                value: entry.to_string(),
+            };
+            let open = entry.find('(').ok_or_else(invalid)?;
+            if !entry.ends_with(')') {
+                return Err(invalid());
+            }
+            let quality = entry[open + 1..entry.len() - 1].parse::<i32>()
+                .map_err(|_| invalid())?;
+
+            Ok(TimecounterChoice { name: entry[..open].to_string(), quality })
+        })
+        .collect()
+}
+
+/// The System V semaphore limits under `kern.seminfo`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SemInfo {
+    /// Most semaphore identifiers.
+    pub semmni: i32,
+    /// Most semaphores in the system.
+    pub semmns: i32,
+    /// Most semaphores per identifier.
+    pub semmsl: i32,
+    /// Most operations per `semop` call.
+    pub semopm: i32,
+    /// Most undo entries per process.
+    pub semume: i32,
+    /// Size of a process's undo structure, in bytes.
+    pub semusz: i32,
+    /// Largest value a semaphore can hold.
+    pub semvmx: i32,
+    /// Largest adjust-on-exit value.
+    pub semaem: i32,
+}
+
+/// Read all of `kern.seminfo` at once.
+pub fn seminfo() -> Result<SemInfo> {
+    Ok(SemInfo {
+        semmni: read("kern.seminfo.semmni")?,
+        semmns: read("kern.seminfo.semmns")?,
+        semmsl: read("kern.seminfo.semmsl")?,
+        semopm: read("kern.seminfo.semopm")?,
+        semume: read("kern.seminfo.semume")?,
+        semusz: read("kern.seminfo.semusz")?,
+        semvmx: read("kern.seminfo.semvmx")?,
+        semaem: read("kern.seminfo.semaem")?,
+    })
+}
+
+/// Write the changeable semaphore limits (`semmni`, `semmns`, `semmsl` and
+/// `semopm`). The rest are fixed when the kernel is built, so if `info`
+/// has them different from what they are now, this fails with
+/// `NotChangeable` before writing anything. Only root may do this.
+pub fn set_seminfo(info: &SemInfo) -> Result<()> {
+    let current = seminfo()?;
+    let fixed = [
+        ("kern.seminfo.semume", info.semume, current.semume),
+        ("kern.seminfo.semusz", info.semusz, current.semusz),
+        ("kern.seminfo.semvmx", info.semvmx, current.semvmx),
+        ("kern.seminfo.semaem", info.semaem, current.semaem),
+    ];
+    if let Some(&(name, _, _)) = fixed.iter().find(|&&(_, new, old)| new != old) {
+        return Err(SysctlError::NotChangeable(name.to_string()));
+    }
+
+    write("kern.seminfo.semmni", &info.semmni)?;
+    write("kern.seminfo.semmns", &info.semmns)?;
+    write("kern.seminfo.semmsl", &info.semmsl)?;
+    write("kern.seminfo.semopm", &info.semopm)
+}
+
+/// The System V shared memory limits under `kern.shminfo`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ShmInfo {
+    /// Largest segment, in bytes.
+    pub shmmax: i32,
+    /// Smallest segment, in bytes.
+    pub shmmin: i32,
+    /// Most segment identifiers.
+    pub shmmni: i32,
+    /// Most segments a process can attach.
+    pub shmseg: i32,
+    /// Most shared memory in the system, in pages.
+    pub shmall: i32,
+}
+
+/// Read all of `kern.shminfo` at once.
+pub fn shminfo() -> Result<ShmInfo> {
+    Ok(ShmInfo {
+        shmmax: read("kern.shminfo.shmmax")?,
+        shmmin: read("kern.shminfo.shmmin")?,
+        shmmni: read("kern.shminfo.shmmni")?,
+        shmseg: read("kern.shminfo.shmseg")?,
+        shmall: read("kern.shminfo.shmall")?,
+    })
+}
+
+/// Write every shared memory limit in `info`. Only root may do this;
+/// anyone else gets `PermissionDenied` from the first write.
+pub fn set_shminfo(info: &ShmInfo) -> Result<()> {
+    write("kern.shminfo.shmmax", &info.shmmax)?;
+    write("kern.shminfo.shmmin", &info.shmmin)?;
+    write("kern.shminfo.shmmni", &info.shmmni)?;
+    write("kern.shminfo.shmseg", &info.shmseg)?;
+    write("kern.shminfo.shmall", &info.shmall)
+}
+
+/// How many seconds the hardware watchdog waits before resetting the
+/// machine (`kern.watchdog.period`), or 0 if it's off. Machines without a
+/// watchdog fail with `EOPNOTSUPP`.
+pub fn watchdog_period() -> Result<i32> {
+    read("kern.watchdog.period")
+}
+
+/// Set the watchdog period in seconds; 0 turns the watchdog off. Only root
+/// may do this.
+pub fn set_watchdog_period(secs: i32) -> Result<()> {
+    if secs < 0 {
+        return Err(SysctlError::InvalidValue {
+            name: "kern.watchdog.period".to_string(),
+            value: secs.to_string(),
+        });
+    }
+
+    write("kern.watchdog.period", &secs)
+}
+
+/// Whether the kernel pats the watchdog itself (`kern.watchdog.auto`). If
+/// not, something in userland has to keep setting the period or the
+/// machine gets reset.
+pub fn watchdog_auto() -> Result<bool> {
+    read("kern.watchdog.auto")
+}
+
+/// Choose whether the kernel pats the watchdog itself. Only root may do
+/// this.
+pub fn set_watchdog_auto(auto: bool) -> Result<()> {
+    write("kern.watchdog.auto", &auto)
+}
+
+/// The system security level (`kern.securelevel`), from -1 (permanently
+/// insecure) to 2 (highly secure).
+pub fn securelevel() -> Result<i32> {
+    read("kern.securelevel")
+}
+
+/// Set the security level, -1 to 2. Once it's above 0 it can only be
+/// raised, so asking for a lower level then fails with `SecurelevelLowered`
+/// without going to the kernel. Only root may do this.
+pub fn set_securelevel(level: i32) -> Result<()> {
+    if !(-1..=2).contains(&level) {
+        return Err(SysctlError::InvalidValue {
+            name: "kern.securelevel".to_string(),
+            value: level.to_string(),
+        });
+    }
+
+    let current = securelevel()?;
+    if current > 0 && level < current {
+        return Err(SysctlError::SecurelevelLowered { current, requested: level });
+    }
+
+    write("kern.securelevel", &level)
+}
+
+// longest name sethostname(2) takes, not counting the NUL
+const MAXHOSTNAMELEN: usize = 255;
+
+/// The machine's hostname (`kern.hostname`).
+pub fn hostname() -> Result<String> {
+    read("kern.hostname")
+}
+
+/// Set the hostname. It has to be non-empty, fit in `MAXHOSTNAMELEN` and
+/// can't have a NUL in it, or it's an `InvalidValue`. Only root may do
+/// this.
+pub fn set_hostname(name: &str) -> Result<()> {
+    write_host_name("kern.hostname", name)
+}
+
+/// The machine's YP/NIS domain name (`kern.domainname`).
+pub fn domainname() -> Result<String> {
+    read("kern.domainname")
+}
+
+/// Set the domain name, under the same rules as `set_hostname`.
+pub fn set_domainname(name: &str) -> Result<()> {
+    write_host_name("kern.domainname", name)
+}
+
+// hostname and domainname are held to the same limits
+fn write_host_name(node: &str, name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAXHOSTNAMELEN || name.contains('\0') {
+        return Err(SysctlError::InvalidValue { name: node.to_string(), value: name.to_string() });
+    }
+
+    // String's encoding adds the terminating NUL
+    write(node, &name.to_string())
+}
+
+/// Kinds of sensor, mirroring `enum sensor_type`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SensorType {
+    Temp,
+    FanRpm,
+    VoltsDc,
+    VoltsAc,
+    Ohms,
+    Watts,
+    Amps,
+    WattHour,
+    AmpHour,
+    Indicator,
+    Integer,
+    Percent,
+    Lux,
+    Drive,
+    TimeDelta,
+    Humidity,
+    Freq,
+    Angle,
+    Distance,
+    Pressure,
+    Accel,
+    Velocity,
+    Energy,
+    /// A type newer than this crate, by its raw `SENSOR_*` number.
+    Other(i32),
+}
+
+const SENSOR_TYPES: [SensorType; 23] = [
+    SensorType::Temp, SensorType::FanRpm, SensorType::VoltsDc,
+    SensorType::VoltsAc, SensorType::Ohms, SensorType::Watts, SensorType::Amps,
+    SensorType::WattHour, SensorType::AmpHour, SensorType::Indicator,
+    SensorType::Integer, SensorType::Percent, SensorType::Lux,
+    SensorType::Drive, SensorType::TimeDelta, SensorType::Humidity,
+    SensorType::Freq, SensorType::Angle, SensorType::Distance,
+    SensorType::Pressure, SensorType::Accel, SensorType::Velocity,
+    SensorType::Energy,
+];
+
+impl SensorType {
+    /// The `SENSOR_*` number the kernel uses for this type.
+    pub fn to_raw(self) -> c_int {
+        match self {
+            SensorType::Other(raw) => raw,
+            _ => SENSOR_TYPES.iter().position(|&t| t == self).unwrap() as c_int,
+        }
+    }
+
+    /// The type for a `SENSOR_*` number, `Other` if we don't know it.
+    pub fn from_raw(raw: c_int) -> SensorType {
+        match SENSOR_TYPES.get(raw as usize) {
+            Some(&kind) if raw >= 0 => kind,
+            _ => SensorType::Other(raw),
+        }
+    }
+
+    /// Units as sysctl(8) prints them. The kernel itself mostly keeps values
+    /// in micro-units, e.g. microkelvin for `Temp`.
+    pub fn units(self) -> &'static str {
+        match self {
+            SensorType::Temp => "degC",
+            SensorType::FanRpm => "RPM",
+            SensorType::VoltsDc => "VDC",
+            SensorType::VoltsAc => "VAC",
+            SensorType::Ohms => "ohm",
+            SensorType::Watts => "W",
+            SensorType::Amps => "A",
+            SensorType::WattHour => "Wh",
+            SensorType::AmpHour => "Ah",
+            SensorType::Indicator => "",
+            SensorType::Integer => "",
+            SensorType::Percent => "%",
+            SensorType::Lux => "lx",
+            SensorType::Drive => "",
+            SensorType::TimeDelta => "secs",
+            SensorType::Humidity => "%",
+            SensorType::Freq => "Hz",
+            SensorType::Angle => "degrees",
+            SensorType::Distance => "m",
+            SensorType::Pressure => "Pa",
+            SensorType::Accel => "m/s^2",
+            SensorType::Velocity => "m/s",
+            SensorType::Energy => "J",
+            SensorType::Other(_) => "",
+        }
+    }
+}
+
+/// Sensor health, mirroring `enum sensor_status`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SensorStatus {
+    Unspec,
+    Ok,
+    Warn,
+    Crit,
+    Unknown,
+}
+
+/// A single sensor reading.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sensor {
+    pub desc: String,
+    /// When the value last changed, since the epoch.
+    pub last_change: Duration,
+    /// The raw value, see `SensorType::units`.
+    pub value: i64,
+    pub kind: SensorType,
+    pub status: SensorStatus,
+    pub numt: i32,
+    pub flags: i32,
+}
+
+// struct sensor, as the kernel lays it out
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawSensor {
+    desc: [c_char; 32],
+    tv: timeval,
+    value: i64,
+    kind: c_int,
+    status: c_int,
+    numt: c_int,
+    flags: c_int,
+}
+
+impl SysctlValue for Sensor {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        let raw: RawSensor = decode_struct(buf)?;
+
+        let kind = SensorType::from_raw(raw.kind);
+        let status = match raw.status {
+            0 => SensorStatus::Unspec,
+            1 => SensorStatus::Ok,
+            2 => SensorStatus::Warn,
+            3 => SensorStatus::Crit,
+            _ => SensorStatus::Unknown,
+        };
+
+        Ok(Sensor {
+            desc: c_chars_to_string(&raw.desc),
+            last_change: timeval_to_duration(&raw.tv),
+            value: raw.value,
+            kind,
+            status,
+            numt: raw.numt,
+            flags: raw.flags,
+        })
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        let mut raw = RawSensor {
+            desc: [0; 32],
+            tv: timeval {
+                tv_sec: self.last_change.as_secs() as time_t,
+                tv_usec: self.last_change.subsec_micros() as suseconds_t,
+            },
+            value: self.value,
+            kind: self.kind.to_raw(),
+            status: self.status as c_int,
+            numt: self.numt,
+            flags: self.flags,
+        };
+        for (dst, src) in raw.desc.iter_mut().zip(self.desc.bytes().take(31)) {
+            *dst = src as c_char;
+        }
+
+        encode_struct(&raw)
+    }
+}
+
+/// Read sensor `idx` of type `kind` on sensor device `dev`
+/// (`hw.sensors.<dev>.<kind>.<idx>`).
+pub fn sensors(dev: u32, kind: SensorType, idx: u32) -> Result<Sensor> {
+    let mib = vec![CTL_HW, HW_SENSORS, dev as c_int, kind.to_raw(), idx as c_int];
+    let sysctl_s = Sysctl::new(mib, SysctlType::SysStruct, false)?;
+
+    Sensor::from_sysctl(&read_mib(&sysctl_s)?)
+}
+
+/// A sensor device (`struct sensordev`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorDev {
+    pub num: i32,
+    pub xname: String,
+    /// How many sensors of each type the device has, indexed by
+    /// `SensorType::to_raw`.
+    pub maxnumt: [i32; 23],
+    pub sensors_count: i32,
+}
+
+impl SensorDev {
+    /// How many sensors of type `kind` this device has.
+    pub fn count(&self, kind: SensorType) -> i32 {
+        match kind.to_raw() {
+            i if i >= 0 => self.maxnumt.get(i as usize).cloned().unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawSensorDev {
+    num: c_int,
+    xname: [c_char; 16],
+    maxnumt: [c_int; 23],
+    sensors_count: c_int,
+}
+
+/// Every sensor device the kernel knows about (`hw.sensors.<dev>`).
+pub fn sensordevs() -> Result<Vec<SensorDev>> {
+    let mut devs = Vec::new();
+
+    // same as sysctl(8): ENXIO is a hole in the numbering (a detached
+    // device), ENOENT means we've gone past the last one
+    for dev in 0.. {
+        let mib = vec![CTL_HW, HW_SENSORS, dev];
+        let sysctl_s = Sysctl::new(mib, SysctlType::SysStruct, false)?;
+
+        let buf = match read_mib(&sysctl_s) {
+            Ok(buf) => buf,
+            Err(SysctlError::Os(Error::Sys(nix::errno::Errno::ENXIO))) => continue,
+            Err(SysctlError::Os(Error::Sys(nix::errno::Errno::ENOENT))) => break,
+            Err(e) => return Err(e),
+        };
+        let raw: RawSensorDev = decode_struct(&buf)?;
+
+        devs.push(SensorDev {
+            num: raw.num,
+            xname: c_chars_to_string(&raw.xname),
+            maxnumt: raw.maxnumt,
+            sensors_count: raw.sensors_count,
+        });
+    }
+
+    Ok(devs)
+}
+
+/// I/O counters for a single disk (`struct diskstats`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiskStats {
+    pub name: String,
+    pub busy: i32,
+    pub rxfer: u64,
+    pub wxfer: u64,
+    pub seek: u64,
+    pub rbytes: u64,
+    pub wbytes: u64,
+    /// When the disk was attached.
+    pub attachtime: Duration,
+    /// When the disk was last unbusied.
+    pub timestamp: Duration,
+    /// Total time spent busy.
+    pub time: Duration,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawDiskStats {
+    name: [c_char; 16],
+    busy: c_int,
+    rxfer: u64,
+    wxfer: u64,
+    seek: u64,
+    rbytes: u64,
+    wbytes: u64,
+    attachtime: timeval,
+    timestamp: timeval,
+    time: timeval,
+}
+
+/// Per-disk I/O counters (`hw.diskstats`), in the same order as the disks
+/// in `hw.disknames`.
+pub fn diskstats() -> Result<Vec<DiskStats>> {
+    let count = read::<i32>("hw.diskcount")?;
+    if count < 0 {
+        return Err(SysctlError::InvalidValue {
+            name: "hw.diskcount".to_string(),
+            value: count.to_string(),
+        });
+    }
+
+    let size = mem::size_of::<RawDiskStats>();
+    let sysctl_s = parse_mib_str("hw.diskstats")?;
+    let buf = read_mib_len(&sysctl_s, count as usize * size)?;
+
+    if buf.len() % size != 0 {
+        return Err(SysctlError::BadLength { expected: count as usize * size, got: buf.len() });
+    }
+
+    buf.chunks(size)
+        .map(|chunk| {
+            let raw: RawDiskStats = decode_struct(chunk)?;
+
+            Ok(DiskStats {
+                name: c_chars_to_string(&raw.name),
+                busy: raw.busy,
+                rxfer: raw.rxfer,
+                wxfer: raw.wxfer,
+                seek: raw.seek,
+                rbytes: raw.rbytes,
+                wbytes: raw.wbytes,
+                attachtime: timeval_to_duration(&raw.attachtime),
+                timestamp: timeval_to_duration(&raw.timestamp),
+                time: timeval_to_duration(&raw.time),
+            })
+        })
+        .collect()
+}
+
+/// A disk from `hw.disknames`, with its DUID if it has one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiskName {
+    pub name: String,
+    pub duid: Option<String>,
+}
+
+/// Every disk's name and DUID (`hw.disknames`).
+pub fn disknames() -> Result<Vec<DiskName>> {
+    Ok(parse_disknames(&read::<String>("hw.disknames")?))
+}
+
+// the kernel gives us something like "sd0:0123456789abcdef,cd0:," where
+// disks without a DUID (or without a disklabel) just get nothing after the
+// colon
+fn parse_disknames(names: &str) -> Vec<DiskName> {
+    names.split(',')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let mut parts = entry.splitn(2, ':');
+            let name = parts.next().unwrap_or("").to_string();
+            let duid = parts.next()
+                .filter(|duid| !duid.is_empty())
+                .map(|duid| duid.to_string());
+
+            DiskName { name, duid }
+        })
+        .collect()
+}
+
+/// How `net.inet.ip.forwarding` treats packets not addressed to us.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Forwarding {
+    Disabled,
+    Enabled,
+    /// Only packets that were processed by IPsec are forwarded.
+    IpsecOnly,
+}
+
+impl SysctlValue for Forwarding {
+    fn from_sysctl(buf: &[u8]) -> Result<Self> {
+        match i32::from_sysctl(buf)? {
+            0 => Ok(Forwarding::Disabled),
+            1 => Ok(Forwarding::Enabled),
+            2 => Ok(Forwarding::IpsecOnly),
+            v => Err(SysctlError::InvalidValue {
+                name: "net.inet.ip.forwarding".to_string(),
+                value: v.to_string(),
+            }),
+        }
+    }
+
+    fn to_sysctl(&self) -> Vec<u8> {
+        (*self as i32).to_sysctl()
+    }
+}
+
+/// Forwarding across both address families.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForwardingState {
+    pub ipv4: Forwarding,
+    pub ipv6: bool,
+}
+
+/// Read `net.inet.ip.forwarding` and `net.inet6.ip6.forwarding` together.
+pub fn forwarding_state() -> Result<ForwardingState> {
+    Ok(ForwardingState {
+        ipv4: read("net.inet.ip.forwarding")?,
+        ipv6: read("net.inet6.ip6.forwarding")?,
+    })
+}
+
+/// How IPv4 packets not addressed to us are forwarded
+/// (`net.inet.ip.forwarding`).
+pub fn ip_forwarding() -> Result<Forwarding> {
+    read("net.inet.ip.forwarding")
+}
+
+/// Set how IPv4 packets not addressed to us are forwarded. Only root may do
+/// this.
+pub fn set_ip_forwarding(f: Forwarding) -> Result<()> {
+    write("net.inet.ip.forwarding", &f)
+}
+
+/// Turn on IPv4 forwarding for all packets.
+pub fn enable_ipv4_forwarding() -> Result<()> {
+    set_ip_forwarding(Forwarding::Enabled)
+}
+
+/// Whether IPv6 packets not addressed to us are forwarded
+/// (`net.inet6.ip6.forwarding`).
+pub fn ip6_forwarding() -> Result<bool> {
+    read("net.inet6.ip6.forwarding")
+}
+
+/// Turn IPv6 forwarding on or off. Only root may do this.
+pub fn set_ip6_forwarding(on: bool) -> Result<()> {
+    write("net.inet6.ip6.forwarding", &on)
+}
+
+/// Whether IPv6 multicast forwarding is on (`net.inet6.ip6.mforwarding`).
+pub fn ip6_mforwarding() -> Result<bool> {
+    read("net.inet6.ip6.mforwarding")
+}
+
+/// Turn IPv6 multicast forwarding on or off. Only root may do this.
+pub fn set_ip6_mforwarding(on: bool) -> Result<()> {
+    write("net.inet6.ip6.mforwarding", &on)
+}
+
+/// Whether path MTU discovery is done for IPv6 multicast
+/// (`net.inet6.ip6.multicast_mtudisc`).
+pub fn ip6_multicast_mtudisc() -> Result<bool> {
+    read("net.inet6.ip6.multicast_mtudisc")
+}
+
+/// Whether IPv6 multipath routing is on (`net.inet6.ip6.multipath`).
+pub fn ip6_multipath() -> Result<bool> {
+    read("net.inet6.ip6.multipath")
+}
+
+/// The IPv6 multicast and multipath flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ip6MulticastConfig {
+    pub mforwarding: bool,
+    pub multicast_mtudisc: bool,
+    pub multipath: bool,
+}
+
+pub fn ip6_multicast_config() -> Result<Ip6MulticastConfig> {
+    Ok(Ip6MulticastConfig {
+        mforwarding: ip6_mforwarding()?,
+        multicast_mtudisc: ip6_multicast_mtudisc()?,
+        multipath: ip6_multipath()?,
+    })
+}
+
+/// A process resource limit that's lower than the kernel-wide limit on the
+/// same resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RlimitShortfall {
+    /// The sysctl holding the kernel limit, e.g. `kern.maxfiles`.
+    pub name: &'static str,
+    pub kernel: u64,
+    /// The process's soft limit.
+    pub soft: u64,
+    /// The process's hard limit.
+    pub hard: u64,
+}
+
+/// Kernel-wide maxima next to where this process is held below them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RlimitReport {
+    pub maxfiles: i32,
+    pub maxproc: i32,
+    /// There's no per-process limit on threads to compare this with.
+    pub maxthread: i32,
+    pub shortfalls: Vec<RlimitShortfall>,
+}
+
+fn getrlimit_checked(resource: c_int) -> Result<rlimit> {
+    let mut rlim = rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::getrlimit(resource, &mut rlim) } < 0 {
+        return Err(last_os_error().into());
+    }
+
+    Ok(rlim)
+}
+
+/// Compare `kern.maxfiles` and `kern.maxproc` with this process's
+/// `RLIMIT_NOFILE` and `RLIMIT_NPROC`, to help tell whether running out of
+/// descriptors or processes is down to the process or the whole system.
+pub fn rlimit_report() -> Result<RlimitReport> {
+    let mut report = RlimitReport {
+        maxfiles: read("kern.maxfiles")?,
+        maxproc: read("kern.maxproc")?,
+        maxthread: read("kern.maxthread")?,
+        shortfalls: Vec::new(),
+    };
+
+    let pairs = [
+        ("kern.maxfiles", report.maxfiles, RLIMIT_NOFILE),
+        ("kern.maxproc", report.maxproc, RLIMIT_NPROC),
+    ];
+    for &(name, kernel, resource) in &pairs {
+        let rlim = getrlimit_checked(resource)?;
+        let kernel = kernel.max(0) as u64;
+        if (rlim.rlim_cur as u64) < kernel {
+            report.shortfalls.push(RlimitShortfall {
+                name,
+                kernel,
+                soft: rlim.rlim_cur as u64,
+                hard: rlim.rlim_max as u64,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+// the port bitmaps (net.inet.*.baddynamic and net.inet.*.rootonly) have one
+// bit per port, lowest port in the lowest bit of the first word
+fn ports_from_bitmap(words: &[u32]) -> Vec<u16> {
+    let mut ports = Vec::new();
+    for (i, &word) in words.iter().enumerate().take(DP_MAPSIZE) {
+        for bit in 0..DP_MAPBITS {
+            if word & (1 << bit) != 0 {
+                ports.push((i * DP_MAPBITS + bit) as u16);
+            }
+        }
+    }
+
+    ports
+}
+
+fn bitmap_from_ports(ports: &[u16]) -> Vec<u32> {
+    let mut words = vec![0u32; DP_MAPSIZE];
+    for &port in ports {
+        let port = port as usize;
+        words[port / DP_MAPBITS] |= 1 << (port % DP_MAPBITS);
+    }
+
+    words
+}
+
+fn read_ports(name: &str) -> Result<Vec<u16>> {
+    Ok(ports_from_bitmap(&read::<Vec<u32>>(name)?))
+}
+
+// port 0 means "pick one for me", so it can't sensibly be reserved
+fn write_ports(name: &str, ports: &[u16], keep_current: bool) -> Result<()> {
+    if ports.contains(&0) {
+        return Err(SysctlError::InvalidValue { name: name.to_string(), value: "0".to_string() });
+    }
+
+    let mut words = bitmap_from_ports(ports);
+    if keep_current {
+        let current = read::<Vec<u32>>(name)?;
+        for (word, old) in words.iter_mut().zip(current) {
+            *word |= old;
+        }
+    }
+
+    write(name, &words)
+}
+
+/// Ports the kernel won't hand out as ephemeral TCP ports
+/// (`net.inet.tcp.baddynamic`), in ascending order.
+pub fn baddynamic_tcp() -> Result<Vec<u16>> {
+    read_ports("net.inet.tcp.baddynamic")
+}
+
+/// Like `baddynamic_tcp`, for UDP (`net.inet.udp.baddynamic`).
+pub fn baddynamic_udp() -> Result<Vec<u16>> {
+    read_ports("net.inet.udp.baddynamic")
+}
+
+/// Replace the set of TCP ports kept out of the ephemeral range. Only root
+/// may do this; anyone else gets `PermissionDenied`. Port 0 is an
+/// `InvalidValue`.
+pub fn set_baddynamic_tcp(ports: &[u16]) -> Result<()> {
+    write_ports("net.inet.tcp.baddynamic", ports, false)
+}
+
+/// Reserve more TCP ports, on top of those already reserved.
+pub fn add_baddynamic_tcp(ports: &[u16]) -> Result<()> {
+    write_ports("net.inet.tcp.baddynamic", ports, true)
+}
+
+/// Like `set_baddynamic_tcp`, for UDP.
+pub fn set_baddynamic_udp(ports: &[u16]) -> Result<()> {
+    write_ports("net.inet.udp.baddynamic", ports, false)
+}
+
+/// Like `add_baddynamic_tcp`, for UDP.
+pub fn add_baddynamic_udp(ports: &[u16]) -> Result<()> {
+    write_ports("net.inet.udp.baddynamic", ports, true)
+}
+
+/// Ports above 1023 that only root may bind for TCP
+/// (`net.inet.tcp.rootonly`). Ports below 1024 are always root-only and
+/// aren't part of this set.
+pub fn tcp_rootonly_ports() -> Result<Vec<u16>> {
+    read_ports("net.inet.tcp.rootonly")
+}
+
+/// Replace the TCP root-only port set. The kernel refuses this once the
+/// securelevel is raised.
+pub fn set_tcp_rootonly_ports(ports: &[u16]) -> Result<()> {
+    write_ports("net.inet.tcp.rootonly", ports, false)
+}
+
+/// Like `tcp_rootonly_ports`, for UDP (`net.inet.udp.rootonly`).
+pub fn udp_rootonly_ports() -> Result<Vec<u16>> {
+    read_ports("net.inet.udp.rootonly")
+}
+
+/// Like `set_tcp_rootonly_ports`, for UDP.
+pub fn set_udp_rootonly_ports(ports: &[u16]) -> Result<()> {
+    write_ports("net.inet.udp.rootonly", ports, false)
+}
+
+/// The secret used to derive stable privacy IPv6 addresses
+/// (`net.inet6.ip6.soiikey`). Only root may read it.
+pub fn soiikey() -> Result<[u8; 16]> {
+    decode_struct(&read::<Vec<u8>>("net.inet6.ip6.soiikey")?)
+}
+
+/// Replace the stable privacy address secret. Only root may do this.
+pub fn set_soiikey(key: &[u8; 16]) -> Result<()> {
+    write("net.inet6.ip6.soiikey", &key.to_vec())
+}
+
+/// Every interface and its addresses, as the stream of `if_msghdr` and
+/// `ifa_msghdr` routing messages the kernel returns for
+/// `net.route.0.0.iflist`.
+pub fn iflist() -> Result<Vec<u8>> {
+    read("net.route.0.0.iflist")
+}
+
+/// The header of one routing message (`struct rt_msghdr`), along with the
+/// whole message, sockaddrs and all.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RtMsg {
+    pub version: u8,
+    /// `RTM_ADD`, `RTM_GET`, and so on.
+    pub kind: u8,
+    pub index: u16,
+    pub tableid: u16,
+    pub priority: u8,
+    /// Which sockaddrs follow the header, as `RTA_*` bits.
+    pub addrs: i32,
+    /// `RTF_*` flags.
+    pub flags: i32,
+    pub pid: pid_t,
+    pub seq: i32,
+    pub errno: i32,
+    pub msg: Vec<u8>,
+}
+
+// everything in rt_msghdr up to and including rtm_errno
+const RT_MSGHDR_MIN: usize = 36;
+
+/// Split a buffer of routing messages, as `net.route` hands back, into
+/// messages, using each one's `rtm_msglen` to find the next.
+fn parse_rt_msgs(buf: &[u8]) -> Result<Vec<RtMsg>> {
+    let u16_at = |b: &[u8], i: usize| u16::from_ne_bytes([b[i], b[i + 1]]);
+    let i32_at = |b: &[u8], i: usize| i32::from_ne_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+
+    let mut msgs = Vec::new();
+    let mut rest = buf;
+    while !rest.is_empty() {
+        let len = if rest.len() >= 2 { u16_at(rest, 0) as usize } else { 0 };
+        // a length that's too short to hold a header (zero included) or
+        // runs off the end means the buffer's been cut short or mangled
+        if len < RT_MSGHDR_MIN || len > rest.len() {
+            return Err(SysctlError::BadLength { expected: len.max(RT_MSGHDR_MIN), got: rest.len() });
+        }
+
+        let msg = &rest[..len];
+        msgs.push(RtMsg {
+            version: msg[2],
+            kind: msg[3],
+            index: u16_at(msg, 6),
+            tableid: u16_at(msg, 8),
+            priority: msg[10],
+            addrs: i32_at(msg, 12),
+            flags: i32_at(msg, 16),
+            pid: i32_at(msg, 24),
+            seq: i32_at(msg, 28),
+            errno: i32_at(msg, 32),
+            msg: msg.to_vec(),
+        });
+        rest = &rest[len..];
+    }
+
+    Ok(msgs)
+}
+
+/// Every route for `af` in routing table `table`, like `route -n show`.
+pub fn route_dump(af: AddrFamily, table: u32) -> Result<Vec<RtMsg>> {
+    let mib = vec![CTL_NET, PF_ROUTE, 0, af.into(), NET_RT_DUMP, 0, table as c_int];
+    let sysctl_s = Sysctl::new(mib, SysctlType::Bytes, false)?;
+
+    parse_rt_msgs(&read_mib(&sysctl_s)?)
+}
+
+/// A kernel debugging variable, from the `debug` tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebugVar {
+    pub index: u32,
+    pub name: String,
+    pub value: i32,
+}
+
+/// Every debugging variable this kernel has (`debug.N.name` and
+/// `debug.N.value`). Kernels built without any have none.
+pub fn debug_vars() -> Result<Vec<DebugVar>> {
+    let mut vars = Vec::new();
+    for index in 0..CTL_DEBUG_MAXID as u32 {
+        // an unused slot is ENOENT, or EOPNOTSUPP on a kernel built
+        // without the debug variables at all
+        let name = match read::<String>(&format!("debug.{}.name", index)) {
+            Err(SysctlError::Os(Error::Sys(nix::errno::Errno::ENOENT)))
+            | Err(SysctlError::Os(Error::Sys(nix::errno::Errno::EOPNOTSUPP))) => continue,
+            res => res?,
+        };
+        let value = read(&format!("debug.{}.value", index))?;
+
+        vars.push(DebugVar { index, name, value });
+    }
+
+    Ok(vars)
+}
+
+// number of NFS procedures, including the NOOP, from <nfs/nfsproto.h>
+const NFS_NPROCS: usize = 23;
+
+/// NFS client and server counters (`struct nfsstats`). The RPC counts are
+/// indexed by NFS procedure number.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NfsStats {
+    pub attrcache_hits: u64,
+    pub attrcache_misses: u64,
+    pub lookupcache_hits: u64,
+    pub lookupcache_misses: u64,
+    pub direofcache_hits: u64,
+    pub direofcache_misses: u64,
+    pub biocache_reads: u64,
+    pub read_bios: u64,
+    pub read_physios: u64,
+    pub biocache_writes: u64,
+    pub write_bios: u64,
+    pub write_physios: u64,
+    pub biocache_readlinks: u64,
+    pub readlink_bios: u64,
+    pub biocache_readdirs: u64,
+    pub readdir_bios: u64,
+    pub rpccnt: [u64; NFS_NPROCS],
+    pub rpcretries: u64,
+    pub srvrpccnt: [u64; NFS_NPROCS],
+    pub srvrpc_errs: u64,
+    pub srv_errs: u64,
+    pub rpcrequests: u64,
+    pub rpctimeouts: u64,
+    pub rpcunexpected: u64,
+    pub rpcinvalid: u64,
+    pub srvcache_inproghits: u64,
+    pub srvcache_idemdonehits: u64,
+    pub srvcache_nonidemdonehits: u64,
+    pub srvcache_misses: u64,
+    pub srvvop_writes: u64,
+}
+
+sysctl_struct!(NfsStats);
+
+/// NFS counters (`vfs.nfs.nfsstats`).
+pub fn nfsstats() -> Result<NfsStats> {
+    read("vfs.nfs.nfsstats")
+}
+
+// a getter and a setter for an int under vfs.ffs
+macro_rules! ffs_tunable {
+    ($(#[$attr:meta])* $get:ident, $leaf:literal) => {
+        $(#[$attr])*
+        pub fn $get() -> Result<i32> {
+            read(concat!("vfs.ffs.", $leaf))
+        }
+    };
+    ($(#[$attr:meta])* $get:ident, $(#[$set_attr:meta])* $set:ident, $leaf:literal) => {
+        ffs_tunable!($(#[$attr])* $get, $leaf);
+
+        $(#[$set_attr])*
+        pub fn $set(v: i32) -> Result<()> {
+            write(concat!("vfs.ffs.", $leaf), &v)
+        }
+    };
+}
+
+ffs_tunable!(
+    /// Most soft update dependencies allowed at once.
+    ffs_max_softdeps,
+    /// Set the soft update dependency limit. Only root may do this.
+    set_ffs_max_softdeps,
+    "max_softdeps"
+);
+ffs_tunable!(
+    /// Ticks between soft update worklist runs.
+    ffs_sd_tickdelay,
+    /// Set the ticks between worklist runs. Only root may do this.
+    set_ffs_sd_tickdelay,
+    "sd_tickdelay"
+);
+
+// the soft update counters are kept by the kernel and can only be read
+ffs_tunable!(
+    /// Times the soft update worklist has been flushed.
+    ffs_sd_worklist_push, "sd_worklist_push"
+);
+ffs_tunable!(
+    /// Times the block dependency limit was neared and the worklist pushed.
+    ffs_sd_blk_limit_push, "sd_blk_limit_push"
+);
+ffs_tunable!(
+    /// Times the inode dependency limit was neared and the worklist pushed.
+    ffs_sd_ino_limit_push, "sd_ino_limit_push"
+);
+ffs_tunable!(
+    /// Times writers were slowed down for hitting the block dependency limit.
+    ffs_sd_blk_limit_hit, "sd_blk_limit_hit"
+);
+ffs_tunable!(
+    /// Times writers were slowed down for hitting the inode dependency limit.
+    ffs_sd_ino_limit_hit, "sd_ino_limit_hit"
+);
+ffs_tunable!(
+    /// Times writers were made to wait for a synchronous flush.
+    ffs_sd_sync_limit_hit, "sd_sync_limit_hit"
+);
+ffs_tunable!(
+    /// Buffers redirtied because indirect block pointers weren't written yet.
+    ffs_sd_indir_blk_ptrs, "sd_indir_blk_ptrs"
+);
+ffs_tunable!(
+    /// Buffers redirtied because the inode bitmap wasn't written yet.
+    ffs_sd_inode_bitmap, "sd_inode_bitmap"
+);
+ffs_tunable!(
+    /// Buffers redirtied because direct block pointers weren't written yet.
+    ffs_sd_direct_blk_ptrs, "sd_direct_blk_ptrs"
+);
+ffs_tunable!(
+    /// Buffers redirtied because a directory entry couldn't be written yet.
+    ffs_sd_dir_entry, "sd_dir_entry"
+);
+
+ffs_tunable!(
+    /// Smallest directory, in bytes, that gets a dirhash.
+    ffs_dirhash_dirsize,
+    /// Set the smallest directory that gets a dirhash. Only root may do this.
+    set_ffs_dirhash_dirsize,
+    "dirhash_dirsize"
+);
+ffs_tunable!(
+    /// Most memory, in bytes, all dirhashes together may use.
+    ffs_dirhash_maxmem,
+    /// Set the dirhash memory limit. Only root may do this.
+    set_ffs_dirhash_maxmem,
+    "dirhash_maxmem"
+);
+
+/// How much memory, in bytes, dirhashes are using right now
+/// (`vfs.ffs.dirhash_mem`). There's no setter; the kernel keeps this
+/// itself.
+pub fn ffs_dirhash_mem() -> Result<i32> {
+    read("vfs.ffs.dirhash_mem")
+}
+
+/// Whether the kernel drops into ddb(4) on a panic (`ddb.panic`), rather
+/// than rebooting.
+pub fn ddb_panic() -> Result<bool> {
+    read("ddb.panic")
+}
+
+/// Choose whether a panic drops into ddb(4). Only root may do this.
+pub fn set_ddb_panic(on: bool) -> Result<()> {
+    write("ddb.panic", &on)
+}
+
+/// Whether ddb(4) can be entered from the console (`ddb.console`). Once
+/// the securelevel is raised this can no longer be turned on.
+pub fn ddb_console() -> Result<bool> {
+    read("ddb.console")
+}
+
+/// Allow or refuse entering ddb(4) from the console. Only root may do
+/// this.
+pub fn set_ddb_console(on: bool) -> Result<()> {
+    write("ddb.console", &on)
+}
+
+/// Whether ddb(4) output also goes to the kernel message buffer
+/// (`ddb.log`).
+pub fn ddb_log() -> Result<bool> {
+    read("ddb.log")
+}
+
+/// Choose whether ddb(4) output is logged. Only root may do this.
+pub fn set_ddb_log(on: bool) -> Result<()> {
+    write("ddb.log", &on)
+}
+
+/// The base ddb(4) prints numbers in (`ddb.radix`).
+pub fn ddb_radix() -> Result<i32> {
+    read("ddb.radix")
+}
+
+/// Set the base ddb(4) prints numbers in. Only root may do this.
+pub fn set_ddb_radix(radix: i32) -> Result<()> {
+    write("ddb.radix", &radix)
+}
+
+/// How wide ddb(4) takes the screen to be, in characters (`ddb.max_width`).
+pub fn ddb_max_width() -> Result<i32> {
+    read("ddb.max_width")
+}
+
+/// Set how wide ddb(4) takes the screen to be. Only root may do this.
+pub fn set_ddb_max_width(width: i32) -> Result<()> {
+    write("ddb.max_width", &width)
+}
+
+/// How many lines ddb(4) prints before pausing (`ddb.max_line`).
+pub fn ddb_max_line() -> Result<i32> {
+    read("ddb.max_line")
+}
+
+/// Set how many lines ddb(4) prints before pausing. Only root may do
+/// this.
+pub fn set_ddb_max_line(lines: i32) -> Result<()> {
+    write("ddb.max_line", &lines)
+}
+
+/// How many columns a tab is in ddb(4) (`ddb.tab_stop_width`).
+pub fn ddb_tab_stop_width() -> Result<i32> {
+    read("ddb.tab_stop_width")
+}
+
+/// Set how many columns a tab is in ddb(4). Only root may do this.
+pub fn set_ddb_tab_stop_width(width: i32) -> Result<()> {
+    write("ddb.tab_stop_width", &width)
+}
+
+fn timeval_to_duration(tv: &timeval) -> Duration {
+    Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000)
+}
+
+// fixed-size C string fields are NUL-padded (or not terminated at all if
+// they're completely full)
+fn c_chars_to_string(chars: &[c_char]) -> String {
+    let bytes: Vec<u8> = chars.iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8)
+        .collect();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_root() -> bool {
+        unsafe { libc::geteuid() == 0 }
+    }
+
+    // a write only root may make should be refused for anyone else
+    fn expect_denied<F: FnOnce() -> Result<()>>(set: F) {
+        if is_root() {
+            return;
+        }
+
+        match set() {
+            Err(SysctlError::PermissionDenied(_)) => {},
+            res => panic!("expected PermissionDenied, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn call_sysctl() {
+        sysctl_read!(get_kern_ostype, "kern.ostype", Vec<u8>);
+
+        let mut buf = vec![0u8; 256];
+        unsafe { get_kern_ostype(&mut buf).unwrap() };
+        buf.dedup_by(|a, b| *a == *b && *b == b'\0');
+
+        assert_eq!(String::from_utf8(buf).unwrap().as_str(), "OpenBSD\0");
+    }
+
+    #[test]
+    fn hw_mib_prefix() {
+        assert_eq!(name_to_mib("hw.ncpu").unwrap(), vec![6, 3]);
+        assert!(read::<i32>("hw.ncpu").unwrap() > 0);
+    }
+
+    #[test]
+    fn empty_string() {
+        // what the kernel hands back for an unset string node
+        assert_eq!(String::from_sysctl(&[]).unwrap(), "");
+        assert_eq!(String::from_sysctl(b"\0").unwrap(), "");
+
+        assert_eq!(read::<String>("kern.nonexistent"),
+                   Err(SysctlError::UnknownName("nonexistent".to_string())));
+    }
+
+    #[test]
+    fn per_cpu_time() {
+        assert_eq!(parse_mib_str("kern.cp_time2.1").unwrap().mib,
+                   vec![CTL_KERN, KERN_CPTIME2, 1]);
+
+        cp_time2(0).unwrap();
+    }
+
+    #[test]
+    fn unnamed_leaf_id() {
+        let sysctl_s = parse_mib_str("net.inet.ip.99").unwrap();
+        assert_eq!(sysctl_s.mib, vec![CTL_NET, PF_INET, IPPROTO_IP, 99]);
+        assert_eq!(sysctl_s.value_type, SysctlType::Bytes);
+
+        // net.inet.ip.ttl, by number
+        assert_eq!(read::<Vec<u8>>("net.inet.ip.3").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn name_cache_stats() {
+        let whole = parse_mib_str("kern.nchstats").unwrap();
+        let field = parse_mib_str("kern.nchstats.good_hits").unwrap();
+        assert_eq!(whole, field);
+        assert!(parse_mib_str("kern.nchstats.bogus").is_err());
+
+        let stats = nchstats().unwrap();
+        assert_eq!(stats.field("good_hits"), Some(stats.good_hits));
+    }
+
+    #[test]
+    fn fork_stats() {
+        assert_eq!(parse_mib_str("kern.forkstat.vforks").unwrap(),
+                   parse_mib_str("kern.forkstat").unwrap());
+
+        let stats = forkstat().unwrap();
+        assert!(stats.forks > 0);
+        assert_eq!(stats.field("vforks"), Some(stats.vforks));
+    }
+
+    #[test]
+    fn vnodes() {
+        let usage = vnode_usage().unwrap();
+        assert!(usage.numvnodes <= usage.maxvnodes);
+
+        let ratio = usage.ratio();
+        assert!((0.0..=1.0).contains(&ratio));
+    }
+
+    #[test]
+    fn tty_counters() {
+        assert_eq!(parse_mib_str("kern.tty.tk_nout").unwrap().mib,
+                   vec![CTL_KERN, KERN_TTY, KERN_TTY_TKNOUT]);
+        assert!(parse_mib_str("kern.tty").is_err());
+
+        tty_stats().unwrap();
+    }
+
+    #[test]
+    fn ipsec_stats() {
+        assert_eq!(AhStat::from_sysctl(&[0u8; 8]),
+                   Err(SysctlError::BadLength { expected: mem::size_of::<AhStat>(), got: 8 }));
+        assert!(EspStat::from_sysctl(&[0u8; 8]).is_err());
+
+        let buf = read::<Vec<u8>>("net.inet.ah.stats").unwrap();
+        assert_eq!(buf.len(), mem::size_of::<AhStat>());
+        let buf = read::<Vec<u8>>("net.inet.esp.stats").unwrap();
+        assert_eq!(buf.len(), mem::size_of::<EspStat>());
+
+        read_ahstat().unwrap();
+        read_espstat().unwrap();
+    }
+
+    #[test]
+    fn proc_table() {
+        let pid = std::process::id() as pid_t;
+        let esize = mem::size_of::<KinfoProc>() as c_int;
+        assert_eq!(parse_mib_str(&format!("kern.proc.pid.{}", pid)).unwrap().mib,
+                   vec![CTL_KERN, KERN_PROC, KERN_PROC_PID, pid, esize, 1]);
+
+        let kp = proc_by_pid(pid).unwrap();
+        assert_eq!(kp.p_pid, pid);
+        assert!(kp.p_vm_rssize > 0);
+
+        assert!(read::<Vec<u8>>("kern.proc.all").unwrap().len() as c_int >= esize);
+    }
+
+    #[test]
+    fn write_read_only() {
+        let mut new = b"Linux\0".to_vec();
+        let res = unsafe {
+            sysctl_raw("kern.ostype",
+                       ptr::null_mut(),
+                       ptr::null_mut(),
+                       new.as_mut_ptr() as *mut c_void,
+                       new.len())
+        };
+
+        assert_eq!(res, Err(SysctlError::NotChangeable("kern.ostype".to_string())));
+    }
+
+    #[test]
+    fn wait_for_changes() {
+        let poll = Duration::from_millis(10);
+
+        // ticks keep on ticking
+        let before = get_value("kern.cp_time").unwrap();
+        let after = wait_for_change("kern.cp_time", poll, Duration::from_secs(5)).unwrap();
+        assert_ne!(after, before);
+
+        let res = wait_for_change("kern.ostype", poll, Duration::from_millis(50));
+        assert_eq!(res, Err(SysctlError::TimedOut));
+    }
+
+    #[test]
+    fn ipsec_firstuse_round_trip() {
+        assert_eq!(set_ipsec_firstuse(Duration::from_secs(1 << 40)), Err(SysctlError::InvalidValue {
+            name: "net.inet.ip.ipsec-firstuse".to_string(),
+            value: (1u64 << 40).to_string(),
+        }));
+
+        if !is_root() {
+            return;
+        }
+
+        let old = ipsec_firstuse().unwrap();
+        set_ipsec_firstuse(old).unwrap();
+        assert_eq!(ipsec_firstuse().unwrap(), old);
+
+        let config = ipsec_config().unwrap();
+        let backwards = IpsecConfig {
+            firstuse: config.soft_firstuse,
+            soft_firstuse: config.firstuse + Duration::from_secs(1),
+        };
+        assert_eq!(set_ipsec_config(&backwards), Err(SysctlError::InvalidValue {
+            name: "net.inet.ip.ipsec-soft-firstuse".to_string(),
+            value: backwards.soft_firstuse.as_secs().to_string(),
+        }));
+    }
+
+    #[test]
+    fn tcp_tuning() {
+        let tuning = read_tcp_tuning().unwrap();
+        assert!(tuning.mssdflt > 0);
+    }
+
+    #[test]
+    fn names_to_mibs() {
+        assert_eq!(name_to_mib("kern.ostype").unwrap(), vec![CTL_KERN, KERN_OSTYPE]);
+
+        let sysctl_s = resolve("kern.hostname").unwrap();
+        assert_eq!(sysctl_s.mib(), &[CTL_KERN, KERN_HOSTNAME]);
+        assert_eq!(sysctl_s.value_type(), &SysctlType::SysString);
+        assert!(sysctl_s.changeable());
+    }
+
+    #[test]
+    fn root_only_read() {
+        if is_root() {
+            return;
+        }
+
+        assert_eq!(read::<Vec<u8>>("net.inet6.ip6.soiikey"),
+                   Err(SysctlError::PermissionDenied("net.inet6.ip6.soiikey".to_string())));
+    }
+
+    #[test]
+    fn mibs_to_names() {
+        assert_eq!(mib_to_name(&[CTL_KERN, KERN_OSTYPE]).unwrap(), "kern.ostype");
+        assert_eq!(mib_to_name(&[1, 2, 3]), Err(SysctlError::UnknownName("1.2.3".to_string())));
+
+        for name in &["kern.hostname", "vm.loadavg", "net.inet.tcp.mssdflt"] {
+            assert_eq!(&mib_to_name(&name_to_mib(name).unwrap()).unwrap(), name);
+        }
+    }
+
+    #[test]
+    fn carp_stats() {
+        assert_eq!(CarpStat::from_sysctl(&[0u8; 8]),
+                   Err(SysctlError::BadLength { expected: mem::size_of::<CarpStat>(), got: 8 }));
+
+        let buf = read::<Vec<u8>>("net.inet.carp.stats").unwrap();
+        assert_eq!(buf.len(), mem::size_of::<CarpStat>());
+
+        read_carpstat().unwrap();
+
+        let stats = super::carp_stats().unwrap();
+        assert!(stats.ipackets + stats.ipackets6 >= stats.badttl);
+    }
+
+    #[test]
+    fn sensor_reads() {
+        assert_eq!(resolve("hw.sensors.0.0.0").unwrap().value_type(), &SysctlType::SysStruct);
+        assert!(resolve("hw.sensors.0.temp.0").is_err());
+
+        for &kind in SENSOR_TYPES.iter() {
+            assert_eq!(SensorType::from_raw(kind.to_raw()), kind);
+        }
+        assert_eq!(SensorType::from_raw(99), SensorType::Other(99));
+        assert_eq!(SensorType::Other(99).to_raw(), 99);
+
+        // not every box has a sensor here, but if it does it had better
+        // decode as the right type
+        if let Ok(sensor) = sensors(0, SensorType::Temp, 0) {
+            assert_eq!(sensor.kind, SensorType::Temp);
+        }
+    }
+
+    #[test]
+    fn cpu_speed() {
+        assert!(cpuspeed_mhz().unwrap() > 0);
+    }
+
+    #[test]
+    fn sensor_devices() {
+        for dev in sensordevs().unwrap() {
+            assert!(!dev.xname.is_empty());
+            assert_eq!(dev.maxnumt.iter().sum::<i32>(), dev.sensors_count);
+        }
+    }
+
+    #[test]
+    fn int_slices() {
+        let buf: Vec<u8> = [1i32, -2, 3].iter().flat_map(|i| i.to_ne_bytes().to_vec()).collect();
+        assert_eq!(Vec::<i32>::from_sysctl(&buf).unwrap(), vec![1, -2, 3]);
+        assert_eq!(Vec::<i32>::from_sysctl(&buf[..5]),
+                   Err(SysctlError::BadLength { expected: 8, got: 5 }));
+    }
+
+    #[test]
+    fn disk_stats() {
+        let stats = diskstats().unwrap();
+        assert_eq!(stats.len() as i32, read::<i32>("hw.diskcount").unwrap());
+        assert!(stats.iter().all(|disk| !disk.name.is_empty()));
+    }
+
+    #[test]
+    fn ipsec_at_a_glance() {
+        assert!(!ipsec_overview().unwrap().enc_alg.is_empty());
+    }
+
+    #[test]
+    fn disk_names() {
+        let names = parse_disknames("sd0:0123456789abcdef,cd0:,vnd0,");
+        assert_eq!(names, vec![
+            DiskName { name: "sd0".to_string(), duid: Some("0123456789abcdef".to_string()) },
+            DiskName { name: "cd0".to_string(), duid: None },
+            DiskName { name: "vnd0".to_string(), duid: None },
+        ]);
+        assert!(parse_disknames("").is_empty());
+
+        assert_eq!(disknames().unwrap().len(), diskstats().unwrap().len());
+    }
+
+    #[test]
+    fn no_unfinished_panics() {
+        // every one of these used to hit an unimplemented!()
+        let names = [
+            "kern.nchstats.good_hits", "kern.nchstats.2passes",
+            "kern.nchstats.nch_dotdothits", "kern.forkstat.forks",
+            "kern.forkstat.kthread_pages", "kern.tty.tk_nin", "kern.tty.tk_cancc",
+            "kern.proc", "kern.proc.", "kern.proc.pid", "kern.proc.pid.x",
+        ];
+
+        for name in names.iter() {
+            assert!(std::panic::catch_unwind(|| resolve(name)).is_ok(), "{} panicked", name);
+        }
+
+        assert!(resolve("kern.proc").is_err());
+        assert!(resolve("kern.proc.pid.x").is_err());
+    }
+
+    #[test]
+    fn ip_counters() {
+        let buf = read::<Vec<u8>>("net.inet.ip.stats").unwrap();
+        assert_eq!(buf.len(), mem::size_of::<IpStat>());
+
+        let stats = ip_stats().unwrap();
+        assert!(stats.total >= stats.badsum);
+    }
+
+    #[test]
+    fn tcp_counters() {
+        let buf = read::<Vec<u8>>("net.inet.tcp.stats").unwrap();
+        assert_eq!(buf.len(), mem::size_of::<TcpStat>());
+
+        let stats = tcp_stats().unwrap();
+        assert!(stats.rcvtotal >= stats.rcvpack);
+    }
+
+    #[test]
+    fn clock_tick_math() {
+        let clock = clockrate().unwrap();
+        let second = clock.tick_duration() * clock.hz();
+        let error = if second > Duration::from_secs(1) {
+            second - Duration::from_secs(1)
+        } else {
+            Duration::from_secs(1) - second
+        };
+        assert!(error < Duration::from_millis(10));
+    }
+
+    #[test]
+    fn forwarding() {
+        let state = forwarding_state().unwrap();
+        assert_eq!(state.ipv4, read("net.inet.ip.forwarding").unwrap());
+        assert_eq!(state.ipv6, read::<bool>("net.inet6.ip6.forwarding").unwrap());
+
+        assert_eq!(Forwarding::from_sysctl(&2i32.to_sysctl()), Ok(Forwarding::IpsecOnly));
+        assert_eq!(Forwarding::from_sysctl(&3i32.to_sysctl()), Err(SysctlError::InvalidValue {
+            name: "net.inet.ip.forwarding".to_string(),
+            value: "3".to_string(),
+        }));
+    }
+
+    #[test]
+    fn udp_icmp_counters() {
+        let udp = udp_stats().unwrap();
+        assert!(udp.ipackets >= udp.hdrops);
+
+        let icmp = icmp_stats().unwrap();
+        assert_eq!(icmp.inhist.len(), ICMP_MAXTYPE + 1);
+
+        let short = vec![0; mem::size_of::<IcmpStat>() - 8];
+        assert_eq!(IcmpStat::from_sysctl(&short), Err(SysctlError::BadLength {
+            expected: mem::size_of::<IcmpStat>(),
+            got: short.len(),
+        }));
+    }
+
+    #[test]
+    fn divert() {
+        let divert = divert_overview().unwrap();
+        assert!(divert.inet.recvspace > 0);
+        assert!(divert.inet6.recvspace > 0);
+    }
+
+    #[test]
+    fn struct_bytes() {
+        let buf = get_struct_bytes("kern.clockrate").unwrap();
+        assert_eq!(buf.len(), mem::size_of::<ClockInfo>());
+        assert_eq!(ClockInfo::from_sysctl(&buf), clockrate());
+
+        assert_eq!(get_struct_bytes("kern.ostype"), Err(SysctlError::WrongType {
+            name: "kern.ostype".to_string(),
+            expected: SysctlType::SysStruct,
+            got: SysctlType::SysString,
+        }));
+    }
+
+    #[test]
+    fn ip6_mforwarding_toggle() {
+        let config = ip6_multicast_config().unwrap();
+        if !is_root() {
+            return;
+        }
+
+        set_ip6_mforwarding(!config.mforwarding).unwrap();
+        assert_eq!(ip6_mforwarding().unwrap(), !config.mforwarding);
+        set_ip6_mforwarding(config.mforwarding).unwrap();
+        assert_eq!(ip6_mforwarding().unwrap(), config.mforwarding);
+    }
+
+    #[test]
+    fn uvm_counters() {
+        let uvm = uvmexp().unwrap();
+        assert_eq!(uvm.pagesize, 1 << uvm.pageshift);
+        assert!(uvm.free > 0 && uvm.free <= uvm.npages);
+        assert!(uvm.free_bytes() > 0);
+
+        // a bigger kernel struct keeps the fields we know about
+        let mut buf = uvm.to_sysctl();
+        buf.extend_from_slice(&[0xff; 16]);
+        assert_eq!(UvmExp::from_sysctl(&buf).unwrap(), uvm);
+    }
+
+    #[test]
+    fn rlimits() {
+        let report = rlimit_report().unwrap();
+        assert!(report.maxfiles > 0 && report.maxproc > 0 && report.maxthread > 0);
+        for shortfall in &report.shortfalls {
+            assert!(shortfall.soft < shortfall.kernel);
+        }
+    }
+
+    #[test]
+    fn vm_totals() {
+        assert!(vmmeter().unwrap().t_free > 0);
+    }
+
+    #[test]
+    fn physical_memory() {
+        let bytes = read::<i64>("hw.physmem").unwrap();
+        // at least 64MB, and a whole number of pages
+        assert!(bytes >= 64 << 20);
+        assert_eq!(bytes % 4096, 0);
+        assert_eq!(physmem().unwrap(), bytes);
+
+        assert_eq!(i64::from_sysctl(&[0u8; 4]),
+                   Err(SysctlError::BadLength { expected: 8, got: 4 }));
+    }
+
+    #[test]
+    fn cp_time_aggregate() {
+        let total = cp_time_total().unwrap();
+        let busiest = *[total.user, total.nice, total.sys, total.spin, total.intr]
+            .iter()
+            .max()
+            .unwrap();
+        assert!(total.idle > busiest);
+    }
+
+    #[test]
+    fn user_memory() {
+        let usermem = usermem().unwrap();
+        assert!(usermem > 0);
+        assert!(usermem <= physmem().unwrap() as u64);
+
+        assert_eq!(u64::from_sysctl(&[0u8; 9]),
+                   Err(SysctlError::BadLength { expected: 8, got: 9 }));
+    }
+
+    #[test]
+    fn cp_time2_out_of_range() {
+        let ncpu = read::<i32>("hw.ncpu").unwrap() as usize;
+        assert_eq!(cp_time2(9999), Err(SysctlError::IndexOutOfRange {
+            name: "kern.cp_time2.9999".to_string(),
+            index: 9999,
+            max: ncpu,
+        }));
+    }
+
+    #[test]
+    fn rootonly_ports() {
+        // NFS is root-only out of the box
+        assert!(tcp_rootonly_ports().unwrap().contains(&2049));
+        assert!(udp_rootonly_ports().unwrap().contains(&2049));
+
+        let ports = vec![0, 31, 32, 2049, 65535];
+        assert_eq!(ports_from_bitmap(&bitmap_from_ports(&ports)), ports);
+    }
+
+    #[test]
+    fn console_device() {
+        let dev = consdev().unwrap();
+        assert_eq!(DevNo::from_raw(dev.raw), dev);
+
+        let dev = DevNo::from_raw(0x0012_0304);
+        assert_eq!((dev.major, dev.minor), (3, 0x1204));
+
+        assert_eq!(DevNo::from_sysctl(&[0u8; 2]), Err(SysctlError::BadLength {
+            expected: mem::size_of::<dev_t>(),
+            got: 2,
+        }));
+    }
+
+    #[test]
+    fn aliased_names() {
+        assert_eq!(parse_mib_str("hw.physmem").unwrap().mib,
+                   parse_mib_str("hw.physmem64").unwrap().mib);
+        assert_eq!(read_with_aliases::<i64>("hw.physmem").unwrap(),
+                   read::<i64>("hw.physmem64").unwrap());
+        assert_eq!(read_with_aliases::<String>("kern.ostype").unwrap(), "OpenBSD");
+    }
+
+    #[test]
+    fn baddynamic_ports() {
+        assert_eq!(read::<Vec<u32>>("net.inet.tcp.baddynamic").unwrap().len(), DP_MAPSIZE);
+
+        for ports in &[baddynamic_tcp().unwrap(), baddynamic_udp().unwrap()] {
+            assert!(!ports.is_empty());
+            assert!(ports.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+
+        let mut words = vec![0u32; DP_MAPSIZE];
+        words[0] = 1 << 22;
+        words[DP_MAPSIZE - 1] = 1 << 31;
+        assert_eq!(ports_from_bitmap(&words), vec![22, 65535]);
+    }
+
+    #[test]
+    fn memory() {
+        let mem = memory_overview().unwrap();
+        assert!(mem.free > 0);
+        assert!(mem.free + mem.active + mem.inactive <= mem.physmem);
+        assert!(mem.loadavg.iter().all(|&load| load >= 0.0));
+    }
+
+    #[test]
+    fn baddynamic_round_trip() {
+        let old = baddynamic_tcp().unwrap();
+        assert_eq!(set_baddynamic_tcp(&[0]), Err(SysctlError::InvalidValue {
+            name: "net.inet.tcp.baddynamic".to_string(),
+            value: "0".to_string(),
+        }));
+
+        expect_denied(|| set_baddynamic_tcp(&old));
+        if !is_root() {
+            return;
+        }
+
+        add_baddynamic_tcp(&[33333, 33334]).unwrap();
+        let added = baddynamic_tcp().unwrap();
+        assert!(added.contains(&33333) && added.contains(&33334));
+        assert!(old.iter().all(|port| added.contains(port)));
+
+        set_baddynamic_tcp(&old).unwrap();
+        assert_eq!(baddynamic_tcp().unwrap(), old);
+    }
+
+    #[test]
+    fn soii_key() {
+        if !is_root() {
+            assert_eq!(soiikey(),
+                       Err(SysctlError::PermissionDenied("net.inet6.ip6.soiikey".to_string())));
+            return;
+        }
+
+        let key = soiikey().unwrap();
+        set_soiikey(&key).unwrap();
+        assert_eq!(soiikey().unwrap(), key);
+    }
+
+    #[test]
+    fn walk_subtree() {
+        let kern = walk("kern").unwrap();
+        assert!(kern.iter().all(|(name, _)| name.starts_with("kern.")));
+        assert!(kern.iter().any(|(name, value)| {
+            name == "kern.ostype" && *value == Value::Str("OpenBSD".to_string())
+        }));
+
+        // root-only nodes are skipped rather than failing the whole walk
+        if !is_root() {
+            let ip6 = walk("net.inet6.ip6").unwrap();
+            assert!(ip6.iter().all(|(name, _)| name != "net.inet6.ip6.soiikey"));
+        }
+
+        assert!(walk("").unwrap().len() > kern.len());
+        assert_eq!(walk("bogus"), Err(SysctlError::UnknownName("bogus".to_string())));
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(get_value("kern.ostype").unwrap(), Value::Str("OpenBSD".to_string()));
+        assert_eq!(get_value("kern.maxproc").unwrap(),
+                   Value::Int(i64::from(read::<i32>("kern.maxproc").unwrap())));
+        assert_eq!(get_value("kern.clockrate").unwrap(),
+                   Value::Struct(clockrate().unwrap().to_sysctl()));
+        match get_value("net.inet.tcp.baddynamic").unwrap() {
+            Value::IntSlice(words) => assert_eq!(words.len(), DP_MAPSIZE),
+            value => panic!("unexpected {:?}", value),
+        }
+    }
+
+    #[test]
+    fn display_values() {
+        assert_eq!(Value::Int(-3).to_string(), "-3");
+        assert_eq!(Value::Str("OpenBSD".to_string()).to_string(), "OpenBSD");
+        assert_eq!(Value::Struct(vec![0xde, 0xad, 0x01]).to_string(), "dead01");
+        assert_eq!(Value::IntSlice(vec![22, 2049]).to_string(), "22,2049");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_values() {
+        let mut values = std::collections::BTreeMap::new();
+        values.insert("hw.ncpu", Value::Int(4));
+        values.insert("kern.ostype", Value::Str("OpenBSD".to_string()));
+        values.insert("net.inet.tcp.rootonly", Value::IntSlice(vec![2049]));
+
+        assert_eq!(serde_json::to_string(&values).unwrap(),
+                   r#"{"hw.ncpu":4,"kern.ostype":"OpenBSD","net.inet.tcp.rootonly":[2049]}"#);
+    }
+
+    #[test]
+    fn raw_mib() {
+        let mut buf = [0u8; 4];
+        assert_eq!(get_raw_mib(&[CTL_HW, HW_NCPU], &mut buf).unwrap(), 4);
+        assert!(i32::from_sysctl(&buf).unwrap() > 0);
+
+        let too_long = vec![CTL_KERN; CTL_MAXNAME as usize + 1];
+        assert_eq!(get_raw_mib(&too_long, &mut buf), Err(SysctlError::BadLength {
+            expected: CTL_MAXNAME as usize,
+            got: CTL_MAXNAME as usize + 1,
+        }));
+    }
+
+    #[test]
+    fn route_args() {
+        let base = [CTL_NET, PF_ROUTE, 0, AF_INET];
+        let with = |tail: &[c_int]| base.iter().chain(tail).cloned().collect::<Vec<c_int>>();
+
+        assert_eq!(name_to_mib("net.route.0.inet.iflist").unwrap(), with(&[NET_RT_IFLIST, 0]));
+        assert_eq!(name_to_mib("net.route.0.inet.iflist.2").unwrap(), with(&[NET_RT_IFLIST, 2]));
+        assert_eq!(name_to_mib("net.route.0.inet.dump.0.1").unwrap(), with(&[NET_RT_DUMP, 0, 1]));
+
+        assert!(!iflist().unwrap().is_empty());
+    }
+
+    #[test]
+    fn route_messages() {
+        let routes = route_dump(AddrFamily::Inet, 0).unwrap();
+        assert!(routes.iter().all(|rtm| rtm.tableid == 0 && rtm.msg.len() >= RT_MSGHDR_MIN));
+
+        let mut buf = vec![0u8; 40];
+        buf[..2].copy_from_slice(&40u16.to_ne_bytes());
+        buf[3] = 4;
+        assert_eq!(parse_rt_msgs(&buf).unwrap()[0].kind, 4);
+
+        // a truncated trailer, and a zero length that would never advance
+        buf.extend_from_slice(&[0u8; 3]);
+        assert!(parse_rt_msgs(&buf).is_err());
+        assert!(parse_rt_msgs(&[0u8; 40]).is_err());
+    }
+
+    #[test]
+    fn addr_families() {
+        assert_eq!("inet6".parse::<AddrFamily>(), Ok(AddrFamily::Inet6));
+        assert_eq!(c_int::from(AddrFamily::Inet6), AF_INET6);
+        assert_eq!(get_addr_family("0").unwrap(), 0);
+        assert_eq!("bogus".parse::<AddrFamily>(),
+                   Err(SysctlError::UnknownName("bogus".to_string())));
+    }
+
+    #[test]
+    fn kmem_stats() {
+        assert_eq!(name_to_mib("kern.malloc.kmemstat.3").unwrap(),
+                   vec![CTL_KERN, KERN_MALLOCSTATS, KERN_MALLOC_KMEMSTAT, 3]);
+
+        let stats = kmemstat(1).unwrap();
+        assert!(stats.calls >= stats.inuse);
+
+        match kmemstat(100_000) {
+            Err(SysctlError::IndexOutOfRange { index, max, .. }) => {
+                assert_eq!(index, 100_000);
+                assert!(max > 1);
+            },
+            res => panic!("expected IndexOutOfRange, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn kmem_names() {
+        assert_eq!(parse_kmemnames("free,devbuf,,pcb,\0"), vec![
+            (0, "free".to_string()),
+            (1, "devbuf".to_string()),
+            (3, "pcb".to_string()),
+        ]);
+
+        let names = kmemnames().unwrap();
+        assert!(names.contains(&"devbuf".to_string()));
+        assert!(names.iter().all(|name| !name.is_empty()));
+    }
+
+    #[test]
+    fn open_files() {
+        let size = mem::size_of::<KinfoFile>() as c_int;
+        assert_eq!(name_to_mib("kern.file").unwrap(),
+                   vec![CTL_KERN, KERN_FILE, KERN_FILE_BYFILE, 0, size, c_int::MAX]);
+
+        assert!(!files().unwrap().is_empty());
+
+        // we've got stdin, stdout and stderr at least
+        let pid = unsafe { libc::getpid() };
+        let ours = files_by_pid(pid).unwrap();
+        assert!(ours.iter().any(|kf| kf.fd_fd == 0));
+    }
+
+    #[test]
+    fn process_args() {
+        assert_eq!(name_to_mib("kern.procargs.1.env").unwrap(),
+                   vec![CTL_KERN, KERN_PROC_ARGS, 1, KERN_PROC_ENV]);
+
+        let pid = unsafe { libc::getpid() };
+        let argv = proc_argv(pid).unwrap();
+        assert_eq!(argv.len(), std::env::args().count());
+        assert!(proc_env(pid).unwrap().iter().all(|var| var.contains('=')));
+
+        let ptr_size = mem::size_of::<*const c_char>();
+        let mut buf = vec![0xffu8; ptr_size * 2];
+        buf.extend(vec![0u8; ptr_size]);
+        buf.extend_from_slice(b"ls\0-l\0");
+        assert_eq!(parse_proc_args(&buf).unwrap(), vec!["ls".to_string(), "-l".to_string()]);
+    }
+
+    #[test]
+    fn vm_map() {
+        let pid = unsafe { libc::getpid() };
+        let map = proc_vmmap(pid).unwrap();
+        assert!(!map.is_empty());
+        assert!(map.iter().all(|kve| kve.start < kve.end));
+        assert!(map.windows(2).all(|pair| pair[0].end <= pair[1].start));
+    }
+
+    #[test]
+    fn handles() {
+        let handle = Handle::new("vm.loadavg").unwrap();
+        assert_eq!(handle.value_type(), &SysctlType::SysStruct);
+        assert!(!handle.is_changeable());
+        assert_eq!(handle.read_bytes().unwrap().len(), mem::size_of::<RawLoadAvg>());
+
+        let handle = Handle::new("kern.ostype").unwrap();
+        assert_eq!(handle.get::<String>().unwrap(), "OpenBSD");
+        assert_eq!(handle.get::<String>().unwrap(), read::<String>("kern.ostype").unwrap());
+
+        assert!(Handle::new("kern.bogus").is_err());
+    }
+
+    #[test]
+    fn handle_string_size() {
+        let handle = Handle::new("kern.ostype").unwrap();
+        assert_eq!(handle.get::<String>().unwrap(), "OpenBSD");
+        assert_eq!(handle.last_len.get(), "OpenBSD\0".len());
+        assert_eq!(handle.get::<String>().unwrap(), "OpenBSD");
+
+        // as if the string had grown since the last read
+        handle.last_len.set(2);
+        assert_eq!(handle.get::<String>().unwrap(), "OpenBSD");
+        assert_eq!(handle.last_len.get(), "OpenBSD\0".len());
+    }
+
+    #[test]
+    fn tcp_oids() {
+        // TCPCTL_* from netinet/tcp_var.h
+        let expected = [
+            ("rfc1323", 1), ("keepinittime", 2), ("keepidle", 3), ("keepintvl", 4),
+            ("slowhz", 5), ("baddynamic", 6), ("recvspace", 7), ("sendspace", 8),
+            ("ident", 9), ("sack", 10), ("mssdflt", 11), ("rstppslimit", 12),
+            ("ackonpush", 13), ("ecn", 14), ("syncachelimit", 15), ("synbucketlimit", 16),
+            ("rfc3390", 17), ("reasslimit", 18), ("drop", 19), ("sackholelimit", 20),
+            ("stats", 21), ("always_keepalive", 22), ("synuselimit", 23), ("rootonly", 24),
+            ("synhashsize", 25),
+        ];
+
+        for &(name, id) in &expected {
+            let name = format!("net.inet.tcp.{}", name);
+            assert_eq!(name_to_mib(&name).unwrap(), vec![CTL_NET, PF_INET, IPPROTO_TCP, id], "{}", name);
+        }
+    }
+
+    #[test]
+    fn ip_oids() {
+        // the net.inet.ip names sysctl -a shows, with IPCTL_* from
+        // netinet/in.h
+        let expected = [
+            ("forwarding", 1), ("redirect", 2), ("ttl", 3), ("sourceroute", 5),
+            ("directed-broadcast", 6), ("portfirst", 7), ("portlast", 8),
+            ("porthifirst", 9), ("porthilast", 10), ("maxqueue", 11), ("encdebug", 12),
+            ("ipsec-expire-acquire", 14), ("ipsec-invalid-life", 15), ("ipsec-pfs", 16),
+            ("ipsec-soft-allocs", 17), ("ipsec-allocs", 18), ("ipsec-soft-bytes", 19),
+            ("ipsec-bytes", 20), ("ipsec-timeout", 21), ("ipsec-soft-timeout", 22),
+            ("ipsec-soft-firstuse", 23), ("ipsec-firstuse", 24), ("ipsec-enc-alg", 25),
+            ("ipsec-auth-alg", 26), ("mtudisc", 27), ("mtudisctimeout", 28),
+            ("ipsec-comp-alg", 29), ("mforwarding", 31), ("multipath", 32), ("stats", 33),
+            ("arptimeout", 39), ("arpdown", 40),
+        ];
+        for &(name, id) in &expected {
+            let name = format!("net.inet.ip.{}", name);
+            assert_eq!(name_to_mib(&name).unwrap(), vec![CTL_NET, PF_INET, IPPROTO_IP, id], "{}", name);
+        }
+        assert!(name_to_mib("net.inet.ip.pirthifirst").is_err());
+
+        // ICMPV6CTL_* from netinet/icmp6.h
+        let expected = [
+            ("redirtimeout", 3), ("nd6_delay", 8), ("nd6_umaxtries", 9),
+            ("nd6_mmaxtries", 10), ("errppslimit", 14), ("nd6_maxnudhint", 15),
+            ("mtudisc_hiwat", 16), ("mtudisc_lowat", 17), ("nd6_debug", 18),
+        ];
+        for &(name, id) in &expected {
+            let name = format!("net.inet6.icmp6.{}", name);
+            assert_eq!(name_to_mib(&name).unwrap(), vec![CTL_NET, PF_INET6, IPPROTO_ICMPV6, id], "{}", name);
+        }
+    }
+
+    #[test]
+    fn pipex_queues() {
+        assert_eq!(name_to_mib("net.pipex.inq.ifq.len").unwrap(),
+                   vec![CTL_NET, PF_PIPEX, PIPEXCTL_INQ, 1]);
+        assert_eq!(name_to_mib("net.pipex.outq.ifq.len").unwrap(),
+                   vec![CTL_NET, PF_PIPEX, PIPEXCTL_OUTQ, 1]);
+        assert_eq!(name_to_mib("net.pipex.outq.drops").unwrap(),
+                   vec![CTL_NET, PF_PIPEX, PIPEXCTL_OUTQ, 3]);
+        assert!(resolve("net.pipex.inq.maxlen").unwrap().changeable());
+        assert!(name_to_mib("net.pipex.outq.ifq").is_err());
+    }
+
+    #[test]
+    fn truncated_names() {
+        assert_eq!(get_value("kern.malloc"),
+                   Err(SysctlError::IncompleteName("kern.malloc".to_string())));
+        assert_eq!(resolve("net.inet"), Err(SysctlError::IncompleteName("net.inet".to_string())));
+        assert_eq!(resolve("kern"), Err(SysctlError::IncompleteName("kern".to_string())));
+
+        for &name in &["net.inet.tcp", "net.inet6.ip6", "net.route", "net.inet.ip.ifq", "hw",
+                       "vm", "vfs.ffs", "vfs.nfs", "machdep", "ddb", "debug", "kern.proc"] {
+            assert_eq!(resolve(name), Err(SysctlError::IncompleteName(name.to_string())));
+        }
+
+        // every way of cutting a known name short is an error, not a panic,
+        // and stopping between segments says the name is incomplete
+        for name in KNOWN_NAMES {
+            let parts: Vec<&str> = name.split('.').collect();
+            for len in 0..parts.len() {
+                let short = parts[..len].join(".");
+                if len > 0 && !KNOWN_NAMES.contains(&short.as_str()) {
+                    match resolve(&short) {
+                        Ok(sysctl_s) => assert_eq!(sysctl_s.value_type(), &SysctlType::Node),
+                        Err(e) => assert_eq!(e, SysctlError::IncompleteName(short.clone())),
+                    }
+                }
+                let _ = resolve(&format!("{}.", short));
+            }
+        }
+    }
+
+    #[test]
+    fn cpu_perf() {
+        assert!(cpuspeed_mhz().unwrap() > 0);
+        assert!(cpuspeed().unwrap() > 0);
+
+        let perf = setperf().unwrap();
+        assert!((0..=100).contains(&perf));
+        assert_eq!(set_setperf(101), Err(SysctlError::InvalidValue {
+            name: "hw.setperf".to_string(),
+            value: "101".to_string(),
+        }));
+
+        expect_denied(|| set_setperf(perf));
+    }
+
+    #[test]
+    fn hostname_roundtrip() {
+        let name = hostname().unwrap();
+        assert!(!name.is_empty());
+
+        for bad in &["".to_string(), "a\0b".to_string(), "a".repeat(256)] {
+            assert_eq!(set_hostname(bad), Err(SysctlError::InvalidValue {
+                name: "kern.hostname".to_string(),
+                value: bad.clone(),
+            }));
+        }
+
+        if !is_root() {
+            return;
+        }
+        set_hostname(&name).unwrap();
+        assert_eq!(hostname().unwrap(), name);
+    }
+
+    #[test]
+    fn domainname_roundtrip() {
+        let name = domainname().unwrap();
+        assert_eq!(set_domainname(""), Err(SysctlError::InvalidValue {
+            name: "kern.domainname".to_string(),
+            value: String::new(),
+        }));
+
+        if !is_root() || name.is_empty() {
+            return;
+        }
+        set_domainname(&name).unwrap();
+        assert_eq!(domainname().unwrap(), name);
+    }
+
+    #[test]
+    fn securelevel_rules() {
+        let level = securelevel().unwrap();
+        assert!((-1..=2).contains(&level));
+        assert_eq!(set_securelevel(3), Err(SysctlError::InvalidValue {
+            name: "kern.securelevel".to_string(),
+            value: "3".to_string(),
+        }));
+
+        if level > 0 {
+            assert_eq!(set_securelevel(level - 1),
+                       Err(SysctlError::SecurelevelLowered { current: level, requested: level - 1 }));
+        }
+    }
+
+    #[test]
+    fn boot_and_uptime() {
+        let booted = boottime().unwrap();
+        assert!(booted > UNIX_EPOCH);
+        assert!(uptime().unwrap() > Duration::ZERO);
+    }
+
+    #[test]
+    fn cpu_counts() {
+        assert!(ncpu().unwrap() > 0);
+        assert!(ncpu_online().unwrap() <= ncpu_found().unwrap());
+    }
+
+    #[test]
+    fn host_byteorder() {
+        let expected = if cfg!(target_endian = "little") { ByteOrder::Little } else { ByteOrder::Big };
+        assert_eq!(byteorder().unwrap(), expected);
+        assert_eq!(ByteOrder::from_sysctl(&1.to_sysctl()), Err(SysctlError::InvalidValue {
+            name: "hw.byteorder".to_string(),
+            value: "1".to_string(),
+        }));
+    }
+
+    #[test]
+    fn power_actions() {
+        for v in -1..5 {
+            assert_eq!(LidAction::from_i32(v).to_i32(), v);
+            assert_eq!(PowerAction::from_i32(v).to_i32(), v);
+        }
+        assert_eq!(LidAction::from_i32(3), LidAction::Unknown(3));
+        assert_eq!(set_lidaction(LidAction::Unknown(3)), Err(SysctlError::InvalidValue {
+            name: "machdep.lidaction".to_string(),
+            value: "3".to_string(),
+        }));
+        assert_eq!(set_pwraction(PowerAction::Unknown(-1)), Err(SysctlError::InvalidValue {
+            name: "machdep.pwraction".to_string(),
+            value: "-1".to_string(),
+        }));
+    }
+
+    #[test]
+    fn aperture() {
+        let v = allowaperture().unwrap();
+        assert!((0..=2).contains(&v));
+        assert_eq!(set_allowaperture(3), Err(SysctlError::InvalidValue {
+            name: "machdep.allowaperture".to_string(),
+            value: "3".to_string(),
+        }));
+    }
+
+    #[test]
+    fn perf_policy() {
+        let policy = perfpolicy().unwrap();
+        assert!(PERF_POLICIES.contains(&policy.as_str()));
+        assert_eq!(set_perfpolicy("turbo"), Err(SysctlError::UnknownName("turbo".to_string())));
+    }
+
+    #[test]
+    fn timecounters() {
+        let parsed = parse_timecounter_choices("i8254(0) tsc(-1000) acpihpet0(1000)").unwrap();
+        assert_eq!(parsed[1], TimecounterChoice { name: "tsc".to_string(), quality: -1000 });
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parse_timecounter_choices("i8254(0) tsc("), Err(SysctlError::InvalidValue {
+            name: "kern.timecounter.choice".to_string(),
+            value: "tsc(".to_string(),
+        }));
+
+        let current = timecounter_hardware().unwrap();
+        assert!(timecounter_choices().unwrap().iter().any(|c| c.name == current));
+        assert_eq!(set_timecounter_hardware("sundial"), Err(SysctlError::InvalidValue {
+            name: "kern.timecounter.hardware".to_string(),
+            value: "sundial".to_string(),
+        }));
+    }
+
+    #[test]
+    fn malloc_options() {
+        let conf = malloc_conf().unwrap();
+        assert!(conf.chars().all(|c| MALLOC_OPTIONS.contains(c)));
+        assert_eq!(set_malloc_conf("Sq"), Err(SysctlError::InvalidValue {
+            name: "vm.malloc_conf".to_string(),
+            value: "Sq".to_string(),
+        }));
+    }
+
+    #[test]
+    fn sem_limits() {
+        let info = seminfo().unwrap();
+        assert!(info.semmni > 0 && info.semmns > 0);
+
+        let changed = SemInfo { semvmx: info.semvmx - 1, ..info };
+        assert_eq!(set_seminfo(&changed),
+                   Err(SysctlError::NotChangeable("kern.seminfo.semvmx".to_string())));
+    }
+
+    #[test]
+    fn shm_limits() {
+        let info = shminfo().unwrap();
+        assert!(info.shmmax >= info.shmmin);
+
+        expect_denied(|| set_shminfo(&info));
+    }
+
+    #[test]
+    fn watchdog() {
+        match watchdog_period() {
+            Ok(period) => assert!(period >= 0),
+            Err(e) => assert_eq!(e, SysctlError::Os(Error::Sys(nix::errno::Errno::EOPNOTSUPP))),
+        }
+        assert_eq!(set_watchdog_period(-1), Err(SysctlError::InvalidValue {
+            name: "kern.watchdog.period".to_string(),
+            value: "-1".to_string(),
+        }));
+    }
+
+    #[test]
+    fn all_inet_stats() {
+        let stats = inet_stats().unwrap();
+        assert!(stats.ip.is_some() && stats.tcp.is_some());
+        assert!(stats.udp.is_some() && stats.icmp.is_some());
+    }
+
+    #[test]
+    fn cpu_time_sampler() {
+        let mut sampler = Sampler::<CpuTime>::new("kern.cp_time").unwrap();
+        let (first, delta) = sampler.sample().unwrap();
+        assert_eq!(delta, None);
+
+        thread::sleep(Duration::from_millis(100));
+        let (second, delta) = sampler.sample().unwrap();
+        assert_eq!(delta, Some(second.delta(&first)));
+        assert!(second.idle >= first.idle);
+    }
+
+    #[test]
+    fn ipv4_forwarding() {
+        let v = ip_forwarding().unwrap();
+        assert_eq!(v, forwarding_state().unwrap().ipv4);
+        assert_eq!(Forwarding::from_sysctl(&v.to_sysctl()), Ok(v));
+
+        expect_denied(|| set_ip_forwarding(v));
+    }
+
+    #[test]
+    fn ipv6_forwarding() {
+        let v = ip6_forwarding().unwrap();
+        assert_eq!(v, forwarding_state().unwrap().ipv6);
+        expect_denied(|| set_ip6_forwarding(v));
+    }
+
+    #[test]
+    fn kernel_version() {
+        let parsed = parse_kernel_version("OpenBSD 7.5 (GENERIC.MP) #82: Wed Mar 20 15:48:40 MDT 2024\n    \
+                                           deraadt@amd64.openbsd.org:/usr/src/sys/arch/amd64/compile/GENERIC.MP\n");
+        assert_eq!(parsed, KernelVersion {
+            os: Some("OpenBSD".to_string()),
+            release: Some("7.5".to_string()),
+            kernel_config: Some("GENERIC.MP".to_string()),
+            build_number: Some(82),
+            build_date: Some("Wed Mar 20 15:48:40 MDT 2024".to_string()),
+            builder: Some("deraadt@amd64.openbsd.org".to_string()),
+        });
+
+        let partial = parse_kernel_version("OpenBSD 7.5 custom\n");
+        assert_eq!((partial.kernel_config, partial.build_number, partial.builder), (None, None, None));
+
+        assert_eq!(version().unwrap().os, Some("OpenBSD".to_string()));
+    }
+
+    #[test]
+    fn os_release() {
+        assert_eq!(parse_osrelease("7.5"), Ok((7, 5)));
+        assert_eq!(parse_osrelease("7.5\0\0"), Ok((7, 5)));
+        for bad in &["7", "7.", "7.5.1", "seven.five"] {
+            assert_eq!(parse_osrelease(bad), Err(SysctlError::InvalidValue {
+                name: "kern.osrelease".to_string(),
+                value: bad.to_string(),
+            }));
+        }
+
+        let (major, minor) = osrelease().unwrap();
+        assert_eq!(format!("{}.{}", major, minor), osrelease_str().unwrap());
+    }
+
+    #[test]
+    fn assignments() {
+        assert_eq!(set_from_str("kern.maxfiles=lots"), Err(SysctlError::InvalidValue {
+            name: "kern.maxfiles".to_string(),
+            value: "lots".to_string(),
+        }));
+        assert_eq!(set_from_str("kern.ostype=Linux"),
+                   Err(SysctlError::NotChangeable("kern.ostype".to_string())));
+        assert_eq!(set_from_str("kern.maxfiles"), Err(SysctlError::InvalidValue {
+            name: "kern.maxfiles".to_string(),
+            value: String::new(),
+        }));
+
+        if !is_root() {
+            return;
+        }
+        let maxfiles = read::<i32>("kern.maxfiles").unwrap();
+        set_from_str(&format!("kern.maxfiles={}", maxfiles)).unwrap();
+        assert_eq!(read::<i32>("kern.maxfiles").unwrap(), maxfiles);
+    }
+
+    #[test]
+    fn os_errors() {
+        assert_eq!(unsafe { libc::close(-1) }, -1);
+        assert_eq!(last_os_error(), Error::Sys(nix::errno::Errno::EBADF));
+
+        // there's no such IP node, so the kernel says so
+        let mut len = 0;
+        let res = unsafe {
+            sysctl_raw("net.inet.ip.250", ptr::null_mut(), &mut len, ptr::null_mut(), 0)
+        };
+        assert_eq!(res, Err(SysctlError::Os(Error::Sys(nix::errno::Errno::EOPNOTSUPP))));
+    }
+
+    #[test]
+    fn ffs_tunables() {
+        assert!(ffs_dirhash_mem().unwrap() <= ffs_dirhash_maxmem().unwrap());
+        assert!(ffs_sd_worklist_push().unwrap() >= 0);
+        assert!(!resolve("vfs.ffs.sd_worklist_push").unwrap().changeable());
+        assert!(resolve("vfs.ffs.max_softdeps").unwrap().changeable());
+
+        if !is_root() {
+            return;
+        }
+        let maxmem = ffs_dirhash_maxmem().unwrap();
+        set_ffs_dirhash_maxmem(maxmem).unwrap();
+        assert_eq!(ffs_dirhash_maxmem().unwrap(), maxmem);
+    }
+
+    #[test]
+    fn nfs_stats() {
+        let stats = nfsstats().unwrap();
+        assert!(stats.rpcrequests >= stats.rpctimeouts);
+        assert_eq!(NfsStats::from_sysctl(&[0; 8]),
+                   Err(SysctlError::BadLength { expected: mem::size_of::<NfsStats>(), got: 8 }));
+    }
+
+    #[test]
+    fn debug_tree() {
+        assert_eq!(name_to_mib("debug.3.value").unwrap(), vec![CTL_DEBUG, 3, CTL_DEBUG_VALUE]);
+        assert_eq!(resolve("debug.20.name"), Err(SysctlError::UnknownName("20".to_string())));
+
+        for var in debug_vars().unwrap() {
+            assert!(!var.name.is_empty());
+        }
+    }
+
+    #[test]
+    fn ddb_settings() {
+        ddb_panic().unwrap();
+        ddb_console().unwrap();
+        ddb_log().unwrap();
+        assert!(ddb_radix().unwrap() > 0);
+        assert!(ddb_tab_stop_width().unwrap() > 0);
+
+        expect_denied(|| set_ddb_log(ddb_log().unwrap()));
+    }
+
+    #[test]
+    fn pool_stats() {
+        assert_eq!(name_to_mib("kern.pool.name.4").unwrap(), vec![CTL_KERN, KERN_POOL, KERN_POOL_NAME, 4]);
+        assert_eq!(name_to_mib("kern.pool.4").unwrap(), vec![CTL_KERN, KERN_POOL, KERN_POOL_POOL, 4]);
+
+        let pools = pools().unwrap();
+        assert!(pools.len() <= read::<i32>("kern.pool.npools").unwrap() as usize);
+        assert!(pools.iter().any(|p| p.name == "mbufpl"));
+        assert!(pools.iter().all(|p| p.stats.size > 0));
+    }
+
+    #[test]
+    fn mbuf_stats() {
+        mbstat().unwrap();
+        assert_eq!(MbStat::from_sysctl(&[0; 24]),
+                   Err(SysctlError::BadLength { expected: mem::size_of::<MbStat>(), got: 24 }));
+    }
+
+    #[test]
+    fn known_names_resolve() {
+        for name in KNOWN_NAMES {
+            let mib = parse_mib_str(name).unwrap().mib;
+            // names can share a MIB, so all we can ask is that it comes back
+            // as one that resolves the same way
+            let back = mib_to_name(&mib).unwrap();
+            assert_eq!(parse_mib_str(&back).unwrap().mib, mib, "{} came back as {}", name, back);
+        }
+    }
+
+    #[test]
+    fn default_structs() {
+        let clock = ClockInfo::default();
+        assert_eq!((clock.hz, clock.tick, clock.stathz, clock.profhz), (0, 0, 0, 0));
     }
 }
@@ -0,0 +1,31 @@
+use std::process::{Command, Output};
+
+fn puffy(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_puffy")).args(args).output().unwrap()
+}
+
+#[test]
+fn print_one() {
+    let out = puffy(&["kern.ostype"]);
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "kern.ostype=OpenBSD\n");
+}
+
+#[test]
+fn print_everything() {
+    let out = puffy(&["-a"]);
+    assert!(out.status.success());
+
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.lines().any(|line| line == "kern.ostype=OpenBSD"));
+    assert!(stdout.lines().any(|line| line.starts_with("hw.ncpu=")));
+}
+
+#[test]
+fn set_read_only() {
+    let out = puffy(&["kern.ostype=x"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    assert_eq!(String::from_utf8(out.stderr).unwrap(),
+               "puffy: kern.ostype=x: kern.ostype is not changeable\n");
+}